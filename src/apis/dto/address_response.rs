@@ -0,0 +1,23 @@
+use crate::apis::dto::transaction_response::TransactionResponse;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A page of the transactions sent by or paying to an address, the pending ones first
+/// and then the confirmed ones from the newest
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AddressHistoryResponse {
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Balance at the tip
+    #[serde(rename = "balance", skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u64>,
+    /// Number of confirmed transactions involving the address
+    #[serde(rename = "transactionCount", skip_serializing_if = "Option::is_none")]
+    pub transaction_count: Option<u64>,
+    #[serde(rename = "offset", skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(rename = "limit", skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(rename = "transactions", skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<Vec<TransactionResponse>>,
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Heights and times to keep the listed blocks within, every bound included
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BlockRangeQuery {
+    #[serde(rename = "fromHeight", skip_serializing_if = "Option::is_none")]
+    pub from_height: Option<usize>,
+    #[serde(rename = "toHeight", skip_serializing_if = "Option::is_none")]
+    pub to_height: Option<usize>,
+    /// Unix time in nanoseconds
+    #[serde(rename = "since", skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    /// Unix time in nanoseconds
+    #[serde(rename = "until", skip_serializing_if = "Option::is_none")]
+    pub until: Option<u64>,
+}
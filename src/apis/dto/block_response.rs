@@ -0,0 +1,86 @@
+use crate::apis::dto::transaction_response::TransactionResponse;
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::{Serialization, TransactionRecord};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Block of the main chain
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BlockResponse {
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
+    /// Hex of the block hash
+    #[serde(rename = "hash", skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Hex of the hash of the block before it
+    #[serde(rename = "previousHash", skip_serializing_if = "Option::is_none")]
+    pub previous_hash: Option<String>,
+    /// Hex of the Merkle root over its transactions
+    #[serde(rename = "merkleRoot", skip_serializing_if = "Option::is_none")]
+    pub merkle_root: Option<String>,
+    /// Unix time in nanoseconds the block was made
+    #[serde(rename = "timeStamp", skip_serializing_if = "Option::is_none")]
+    pub time_stamp: Option<u128>,
+    #[serde(rename = "nonce", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<i32>,
+    /// Compact form of the target its hash meets
+    #[serde(rename = "target", skip_serializing_if = "Option::is_none")]
+    pub target: Option<u32>,
+    #[serde(rename = "transactionCount", skip_serializing_if = "Option::is_none")]
+    pub transaction_count: Option<usize>,
+    /// Serialized size of the block in bytes
+    #[serde(rename = "size", skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    /// Number of blocks from this one up to the tip
+    #[serde(rename = "confirmations", skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<usize>,
+    /// Missing when blocks are listed
+    #[serde(rename = "transactions", skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<Vec<TransactionResponse>>,
+}
+
+impl BlockResponse {
+    /// The block at `height` of a chain of `chain_len` blocks, without its transactions.
+    pub fn from_block(height: usize, block: &Block, chain_len: usize) -> Self {
+        BlockResponse {
+            height: Some(height),
+            hash: Some(hex::encode(block.hash())),
+            previous_hash: Some(hex::encode(&block.header.previous_hash)),
+            merkle_root: Some(hex::encode(&block.header.merkle_root)),
+            time_stamp: Some(block.header.time_stamp),
+            nonce: Some(block.header.nonce),
+            target: Some(block.header.target),
+            transaction_count: Some(block.transactions.len()),
+            size: Some(block.serialization().len()),
+            confirmations: Some(chain_len - height),
+            transactions: None,
+        }
+    }
+
+    pub fn with_transactions(mut self, records: &[TransactionRecord]) -> Self {
+        self.transactions = Some(
+            records
+                .iter()
+                .map(TransactionResponse::from_record)
+                .collect(),
+        );
+        self
+    }
+}
+
+/// A page of the main chain blocks, the newest first
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BlockListResponse {
+    /// Height of the tip
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
+    /// Number of blocks within the heights and times asked, not only in this page
+    #[serde(rename = "count", skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    #[serde(rename = "offset", skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(rename = "limit", skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(rename = "blocks", skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<Vec<BlockResponse>>,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// New wallets derived from a fresh mnemonic, stored encrypted with a password
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HdWalletRequest {
+    #[serde(rename = "password", skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Salts the seed along with the mnemonic, empty when missing
+    #[serde(rename = "passphrase", skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    /// Words of the mnemonic, 12, 15, 18, 21 or 24, 12 when missing
+    #[serde(rename = "words", skip_serializing_if = "Option::is_none")]
+    pub words: Option<usize>,
+    /// Receiving addresses to derive and store, 1 when missing and at most 20
+    #[serde(rename = "count", skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+}
+
+impl HdWalletRequest {
+    pub const MAX_COUNT: u32 = 20;
+}
+
+/// Mnemonic to restore the wallets of, stored encrypted with a password
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HdRecoverRequest {
+    #[serde(rename = "mnemonic", skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    #[serde(rename = "passphrase", skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    #[serde(rename = "password", skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Unused addresses in a row after which the scan stops, 20 when missing and at most 1000
+    #[serde(rename = "gapLimit", skip_serializing_if = "Option::is_none")]
+    pub gap_limit: Option<u32>,
+}
+
+impl HdRecoverRequest {
+    pub const MAX_GAP_LIMIT: u32 = 1_000;
+}
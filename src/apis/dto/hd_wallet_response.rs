@@ -0,0 +1,43 @@
+use crate::core::blockchain::balance::AccountState;
+use crate::core::wallet::hd::HdWallet;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A receiving address derived from a seed
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DerivedAddress {
+    #[serde(rename = "index", skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    /// Derivation path from the master key, like `m/44'/0'/0'/0/1`
+    #[serde(rename = "path", skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(rename = "balance", skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u64>,
+    #[serde(rename = "transactionCount", skip_serializing_if = "Option::is_none")]
+    pub transaction_count: Option<u64>,
+}
+
+impl DerivedAddress {
+    pub fn from_account(index: u32, address: &str, account: AccountState) -> Self {
+        DerivedAddress {
+            index: Some(index),
+            path: Some(HdWallet::receive_path(index).to_string()),
+            address: Some(address.to_string()),
+            balance: Some(account.balance),
+            transaction_count: Some(account.tx_count),
+        }
+    }
+}
+
+/// Wallets derived from a seed and stored by the node. The mnemonic is only returned, in
+/// plaintext, when it was just generated, it is not stored and is the only way to restore
+/// them
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HdWalletResponse {
+    #[serde(rename = "mnemonic", skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    #[serde(rename = "addresses", skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<DerivedAddress>>,
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Optional block height to answer a query at, the tip when missing
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HeightQuery {
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
+}
@@ -0,0 +1,81 @@
+use crate::apis::dto::transaction_response::TransactionOutput;
+use crate::core::blockchain::mempool::{Mempool, MempoolEntry};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Transaction waiting for a block
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PendingTransaction {
+    #[serde(rename = "txid", skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    #[serde(rename = "sender", skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    #[serde(rename = "nonce", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    #[serde(rename = "fee", skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+    /// Serialized size of the transaction in bytes
+    #[serde(rename = "size", skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    /// Fee per byte
+    #[serde(rename = "feeRate", skip_serializing_if = "Option::is_none")]
+    pub fee_rate: Option<f64>,
+    /// Unix time in seconds the node accepted the transaction
+    #[serde(rename = "receivedAt", skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<u64>,
+    #[serde(rename = "outputs", skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<TransactionOutput>>,
+}
+
+impl PendingTransaction {
+    pub fn from_entry(entry: &MempoolEntry) -> Self {
+        let tx = &entry.transaction;
+        PendingTransaction {
+            txid: Some(hex::encode(&entry.txid)),
+            sender: Some(String::from_utf8_lossy(&tx.sender_address).into_owned()),
+            nonce: Some(tx.nonce),
+            fee: Some(tx.fee),
+            size: Some(entry.size()),
+            fee_rate: Some(entry.fee_rate()),
+            received_at: Some(entry.received),
+            outputs: Some(
+                tx.outputs
+                    .iter()
+                    .map(|output| TransactionOutput {
+                        address: Some(String::from_utf8_lossy(&output.address).into_owned()),
+                        value: Some(output.value),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Transactions waiting for a block, the best paying per byte first
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MempoolResponse {
+    #[serde(rename = "count", skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    /// Serialized size of every pending transaction in bytes
+    #[serde(rename = "size", skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    #[serde(rename = "transactions", skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<Vec<PendingTransaction>>,
+}
+
+impl MempoolResponse {
+    pub fn from_mempool(mempool: &Mempool) -> Self {
+        let mut entries: Vec<&MempoolEntry> = mempool.iter().collect();
+        entries.sort_by(|a, b| b.cmp_fee_rate(a));
+        MempoolResponse {
+            count: Some(mempool.len()),
+            size: Some(mempool.size()),
+            transactions: Some(
+                entries
+                    .into_iter()
+                    .map(PendingTransaction::from_entry)
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How to mine in the background
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MiningStartRequest {
+    /// `continuous`, `interval:<seconds>` or `mempool:<transactions>`, the configured
+    /// mode or `continuous` when missing
+    #[serde(rename = "mode", skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Address the coinbase of the mined blocks pays to, unchanged when missing
+    #[serde(rename = "minerAddress", skip_serializing_if = "Option::is_none")]
+    pub miner_address: Option<String>,
+}
@@ -0,0 +1,76 @@
+use crate::core::miner::auto::AutoMiner;
+use crate::core::miner::MinedBlock;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Block mined by this node
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MiningResponse {
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
+    /// Hex of the block hash
+    #[serde(rename = "hash", skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(rename = "nonce", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<i32>,
+    #[serde(rename = "transactionCount", skip_serializing_if = "Option::is_none")]
+    pub transaction_count: Option<usize>,
+    /// Number of hashes computed to find the block
+    #[serde(rename = "hashes", skip_serializing_if = "Option::is_none")]
+    pub hashes: Option<u64>,
+    /// Hashes per second
+    #[serde(rename = "hashRate", skip_serializing_if = "Option::is_none")]
+    pub hash_rate: Option<f64>,
+    #[serde(rename = "elapsedMs", skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+}
+
+impl MiningResponse {
+    pub fn from_mined(mined: &MinedBlock) -> Self {
+        MiningResponse {
+            height: Some(mined.height),
+            hash: Some(hex::encode(mined.block.hash())),
+            nonce: Some(mined.block.header.nonce),
+            transaction_count: Some(mined.block.transactions.len()),
+            hashes: Some(mined.stats.hashes),
+            hash_rate: Some(mined.stats.hash_rate()),
+            elapsed_ms: Some(mined.stats.elapsed.as_millis() as u64),
+        }
+    }
+}
+
+/// State of the background mining of this node
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MiningStatusResponse {
+    /// Whether blocks are mined in the background
+    #[serde(rename = "running", skip_serializing_if = "Option::is_none")]
+    pub running: Option<bool>,
+    /// `continuous`, `interval:<seconds>` or `mempool:<transactions>` while running
+    #[serde(rename = "mode", skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Whether a block is being searched right now
+    #[serde(rename = "mining", skip_serializing_if = "Option::is_none")]
+    pub mining: Option<bool>,
+    /// Hashes per second of the current search, or of the last one
+    #[serde(rename = "hashRate", skip_serializing_if = "Option::is_none")]
+    pub hash_rate: Option<f64>,
+    /// Blocks mined in the background since the node started
+    #[serde(rename = "blocksMined", skip_serializing_if = "Option::is_none")]
+    pub blocks_mined: Option<u64>,
+    #[serde(rename = "minerAddress", skip_serializing_if = "Option::is_none")]
+    pub miner_address: Option<String>,
+}
+
+impl MiningStatusResponse {
+    pub fn from_auto_miner(auto_miner: &AutoMiner, miner_address: &str) -> Self {
+        let mode = auto_miner.mode();
+        MiningStatusResponse {
+            running: Some(mode.is_some()),
+            mode: mode.map(|m| m.to_string()),
+            mining: Some(auto_miner.miner().is_mining()),
+            hash_rate: Some(auto_miner.miner().hash_rate()),
+            blocks_mined: Some(auto_miner.blocks_mined()),
+            miner_address: Some(miner_address.to_string()),
+        }
+    }
+}
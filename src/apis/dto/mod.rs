@@ -1,9 +1,22 @@
+pub mod address_response;
+pub mod block_range_query;
+pub mod block_response;
+pub mod hd_wallet_request;
+pub mod hd_wallet_response;
+pub mod height_query;
+pub mod mempool_response;
+pub mod mining_request;
+pub mod mining_response;
+pub mod page_query;
 pub mod query_amount;
 pub mod query_amount_response;
+pub mod signed_transaction;
 pub mod transaction;
 pub mod transaction_request;
+pub mod transaction_response;
 pub mod transactions_in_block_chain_response;
 pub mod wallet;
+pub mod wallet_request;
 pub mod wallet_response;
 
 pub use transaction::Transaction;
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Slice of a list, newest first
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PageQuery {
+    /// Number of entries to skip, 0 when missing
+    #[serde(rename = "offset", skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    /// Most entries to return, 20 when missing and at most 100
+    #[serde(rename = "limit", skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl PageQuery {
+    pub const DEFAULT_LIMIT: usize = 20;
+    pub const MAX_LIMIT: usize = 100;
+
+    pub fn offset(&self) -> usize {
+        self.offset.unwrap_or(0)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .min(Self::MAX_LIMIT)
+    }
+}
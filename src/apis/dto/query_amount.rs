@@ -15,11 +15,29 @@ use serde::{Deserialize, Serialize};
 pub struct QueryAmount {
     /// The amount of currency in the wallet for the provided address
     #[serde(rename = "amount", skip_serializing_if = "Option::is_none")]
-    pub amount: Option<f32>,
+    pub amount: Option<u64>,
+    /// The number of transfers sent from the address
+    #[serde(rename = "nonce", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// The nonce the next transaction of the address must carry, counting pending ones
+    #[serde(rename = "nextNonce", skip_serializing_if = "Option::is_none")]
+    pub next_nonce: Option<u64>,
+    /// The number of transactions paying to or spending from the address
+    #[serde(rename = "transactionCount", skip_serializing_if = "Option::is_none")]
+    pub transaction_count: Option<u64>,
+    /// The block height the figures were taken at
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
 }
 
 impl QueryAmount {
     pub fn new() -> QueryAmount {
-        QueryAmount { amount: None }
+        QueryAmount {
+            amount: None,
+            nonce: None,
+            next_nonce: None,
+            transaction_count: None,
+            height: None,
+        }
     }
 }
@@ -13,12 +13,31 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct QueryAmountResponse {
+    /// Balance of the address, in whole units
     #[serde(rename = "amount", skip_serializing_if = "Option::is_none")]
-    pub amount: Option<f32>,
+    pub amount: Option<u64>,
+    /// Number of transfers sent from the address
+    #[serde(rename = "nonce", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// Nonce the next transaction of the address must carry, counting pending ones
+    #[serde(rename = "nextNonce", skip_serializing_if = "Option::is_none")]
+    pub next_nonce: Option<u64>,
+    /// Number of transactions paying to or spending from the address
+    #[serde(rename = "transactionCount", skip_serializing_if = "Option::is_none")]
+    pub transaction_count: Option<u64>,
+    /// Height of the block the figures were taken at
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
 }
 
 impl QueryAmountResponse {
     pub fn new() -> QueryAmountResponse {
-        QueryAmountResponse { amount: None }
+        QueryAmountResponse {
+            amount: None,
+            nonce: None,
+            next_nonce: None,
+            transaction_count: None,
+            height: None,
+        }
     }
 }
@@ -0,0 +1,54 @@
+use crate::core::wallet::scheme::Scheme;
+use crate::core::wallet::WalletTransaction;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Transaction signed by the wallet of the sender, the private key never leaves it
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SignedTransaction {
+    #[serde(rename = "sender", skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    #[serde(rename = "recipient", skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    #[serde(rename = "amount", skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    /// Paid to the miner on top of the amount, zero when missing
+    #[serde(rename = "fee", skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+    /// Number of transfers the sender made before this one, confirmed or pending
+    #[serde(rename = "nonce", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// Signature scheme of the key of the sender: p256, secp256k1 or ed25519, p256 when
+    /// missing
+    #[serde(rename = "scheme", skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    /// Hex of the public key of the sender, the x and y coordinates for p256 and
+    /// secp256k1, the 32 byte key for ed25519
+    #[serde(rename = "publicKey", skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Hex of the signature over the signing payload of the transaction, for the chain id
+    /// of the network of the node
+    #[serde(rename = "signature", skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl SignedTransaction {
+    /// The signed wallet transaction, `None` when a field is missing or the scheme is
+    /// unknown.
+    pub fn to_wallet_transaction(&self) -> Option<WalletTransaction> {
+        let scheme = match &self.scheme {
+            Some(scheme) => scheme.parse().ok()?,
+            None => Scheme::default(),
+        };
+        Some(WalletTransaction {
+            sender: self.sender.clone()?,
+            recipient: self.recipient.clone()?,
+            amount: self.amount?,
+            fee: self.fee.unwrap_or_default(),
+            nonce: self.nonce?,
+            scheme,
+            public_key: self.public_key.clone()?,
+            signature: self.signature.clone()?,
+        })
+    }
+}
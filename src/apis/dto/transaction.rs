@@ -9,6 +9,11 @@ pub struct Transaction {
     pub recipient: Option<String>,
     #[serde(rename = "amount", skip_serializing_if = "Option::is_none")]
     pub amount: Option<f64>,
+    #[serde(rename = "fee", skip_serializing_if = "Option::is_none")]
+    pub fee: Option<f64>,
+    /// Signature scheme of the private key: p256, secp256k1 or ed25519, p256 when missing
+    #[serde(rename = "scheme", skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
     #[serde(rename = "publicKey", skip_serializing_if = "Option::is_none")]
     pub public_key: Option<String>,
     #[serde(rename = "privateKey", skip_serializing_if = "Option::is_none")]
@@ -0,0 +1,101 @@
+use crate::core::blockchain::transaction::TxKind;
+use crate::core::blockchain::TransactionRecord;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Output of an earlier transaction spent by a transaction
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TransactionInput {
+    #[serde(rename = "txid", skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    #[serde(rename = "index", skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+}
+
+/// Amount a transaction pays to an address
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TransactionOutput {
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(rename = "value", skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+}
+
+/// Transaction found by its txid
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TransactionResponse {
+    #[serde(rename = "txid", skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    /// Height of the block the transaction is the coinbase of, missing for a transfer
+    #[serde(rename = "coinbaseHeight", skip_serializing_if = "Option::is_none")]
+    pub coinbase_height: Option<u64>,
+    /// Missing for a coinbase
+    #[serde(rename = "sender", skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    #[serde(rename = "nonce", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// Paid to the miner, what the inputs hold beyond the outputs
+    #[serde(rename = "fee", skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+    #[serde(rename = "inputs", skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Vec<TransactionInput>>,
+    #[serde(rename = "outputs", skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<TransactionOutput>>,
+    /// Signature scheme of the key of the sender: p256, secp256k1 or ed25519
+    #[serde(rename = "scheme", skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    #[serde(rename = "publicKey", skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(rename = "signature", skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Height of the block holding the transaction, missing while it is pending
+    #[serde(rename = "blockHeight", skip_serializing_if = "Option::is_none")]
+    pub block_height: Option<usize>,
+    /// Position of the transaction in its block, missing while it is pending
+    #[serde(rename = "position", skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    /// Number of blocks from its block up to the tip, zero while it is pending
+    #[serde(rename = "confirmations", skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<usize>,
+}
+
+impl TransactionResponse {
+    pub fn from_record(record: &TransactionRecord) -> Self {
+        let tx = &record.transaction;
+        TransactionResponse {
+            txid: Some(hex::encode(tx.txid())),
+            coinbase_height: match tx.kind {
+                TxKind::Coinbase { height } => Some(height),
+                TxKind::Transfer => None,
+            },
+            sender: (!tx.is_coinbase())
+                .then(|| String::from_utf8_lossy(&tx.sender_address).into_owned()),
+            nonce: Some(tx.nonce),
+            fee: Some(tx.fee),
+            inputs: Some(
+                tx.inputs
+                    .iter()
+                    .map(|input| TransactionInput {
+                        txid: Some(hex::encode(&input.txid)),
+                        index: Some(input.index),
+                    })
+                    .collect(),
+            ),
+            outputs: Some(
+                tx.outputs
+                    .iter()
+                    .map(|output| TransactionOutput {
+                        address: Some(String::from_utf8_lossy(&output.address).into_owned()),
+                        value: Some(output.value),
+                    })
+                    .collect(),
+            ),
+            scheme: Some(tx.scheme.to_string()),
+            public_key: Some(hex::encode(&tx.public_key)),
+            signature: Some(hex::encode(&tx.signature)),
+            block_height: record.location.map(|(height, _)| height),
+            position: record.location.map(|(_, position)| position),
+            confirmations: Some(record.confirmations),
+        }
+    }
+}
@@ -8,16 +8,18 @@
  * Generated by: https://openapi-generator.tech
  */
 
-use crate::apis::dto::Transaction;
+use crate::apis::dto::transaction_response::TransactionResponse;
+use crate::core::blockchain::TransactionRecord;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct TransactionsInBlockChainResponse {
+    /// Number of transactions in the whole list, not only in this page
     #[serde(rename = "transaction_count", skip_serializing_if = "Option::is_none")]
-    pub transaction_count: Option<i32>,
+    pub transaction_count: Option<usize>,
     #[serde(rename = "transactions", skip_serializing_if = "Option::is_none")]
-    pub transactions: Option<Vec<Transaction>>,
+    pub transactions: Option<Vec<TransactionResponse>>,
 }
 
 impl TransactionsInBlockChainResponse {
@@ -27,4 +29,16 @@ impl TransactionsInBlockChainResponse {
             transactions: None,
         }
     }
+
+    pub fn from_records(transaction_count: usize, records: &[TransactionRecord]) -> Self {
+        TransactionsInBlockChainResponse {
+            transaction_count: Some(transaction_count),
+            transactions: Some(
+                records
+                    .iter()
+                    .map(TransactionResponse::from_record)
+                    .collect(),
+            ),
+        }
+    }
 }
@@ -1,23 +1,37 @@
+use crate::core::wallet::address::Address;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-/// Wallet API model
+/// Wallet API model, its private key never leaves the keystore of the node
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct Wallet {
     #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
     pub address: Option<String>,
+    /// Signature scheme of the key: p256, secp256k1 or ed25519
+    #[serde(rename = "scheme", skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
     #[serde(rename = "publicKey", skip_serializing_if = "Option::is_none")]
     pub public_key: Option<String>,
-    #[serde(rename = "privateKey", skip_serializing_if = "Option::is_none")]
-    pub private_key: Option<String>,
 }
 
 impl Wallet {
-    pub fn new_from(address: &str, public_key: &str, private_key: &str) -> Self {
+    pub fn new_from(address: &str, public_key: &str) -> Self {
         Wallet {
             address: Some(address.to_string()),
+            scheme: address
+                .parse::<Address>()
+                .ok()
+                .map(|address| address.scheme().to_string()),
             public_key: Some(public_key.to_string()),
-            private_key: Some(private_key.to_string()),
         }
     }
 }
+
+/// Addresses of the wallets stored by the node
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WalletListResponse {
+    #[serde(rename = "count", skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    #[serde(rename = "addresses", skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<String>>,
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Password encrypting a new wallet in the keystore of the node, and the scheme of its key
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WalletRequest {
+    #[serde(rename = "password", skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Signature scheme of the new key: p256, secp256k1 or ed25519, p256 when missing
+    #[serde(rename = "scheme", skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+}
+
+/// Current and new password of a stored wallet
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PasswordChangeRequest {
+    #[serde(rename = "password", skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(rename = "newPassword", skip_serializing_if = "Option::is_none")]
+    pub new_password: Option<String>,
+}
+
+/// Transfer signed by a stored wallet, opened with its password
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WalletTransferRequest {
+    #[serde(rename = "password", skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(rename = "recipient", skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    #[serde(rename = "amount", skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    /// Paid to the miner, zero when missing
+    #[serde(rename = "fee", skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+}
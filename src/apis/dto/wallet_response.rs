@@ -16,8 +16,6 @@ pub struct WalletResponse {
     pub address: Option<String>,
     #[serde(rename = "publicKey", skip_serializing_if = "Option::is_none")]
     pub public_key: Option<String>,
-    #[serde(rename = "privateKey", skip_serializing_if = "Option::is_none")]
-    pub private_key: Option<String>,
 }
 
 impl WalletResponse {
@@ -25,15 +23,13 @@ impl WalletResponse {
         WalletResponse {
             address: None,
             public_key: None,
-            private_key: None,
         }
     }
 
-    pub fn new_from(address: String, public_key: String, private_key: String) -> WalletResponse {
+    pub fn new_from(address: String, public_key: String) -> WalletResponse {
         WalletResponse {
             address: Some(address),
             public_key: Some(public_key),
-            private_key: Some(private_key),
         }
     }
 }
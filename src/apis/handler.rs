@@ -1,50 +1,425 @@
+use crate::apis::dto::address_response::AddressHistoryResponse;
+use crate::apis::dto::block_range_query::BlockRangeQuery;
+use crate::apis::dto::block_response::{BlockListResponse, BlockResponse};
+use crate::apis::dto::hd_wallet_request::{HdRecoverRequest, HdWalletRequest};
+use crate::apis::dto::hd_wallet_response::{DerivedAddress, HdWalletResponse};
+use crate::apis::dto::height_query::HeightQuery;
+use crate::apis::dto::mempool_response::MempoolResponse;
+use crate::apis::dto::mining_request::MiningStartRequest;
+use crate::apis::dto::mining_response::{MiningResponse, MiningStatusResponse};
+use crate::apis::dto::page_query::PageQuery;
 use crate::apis::dto::query_amount_response::QueryAmountResponse;
+use crate::apis::dto::signed_transaction::SignedTransaction;
+use crate::apis::dto::transaction_response::TransactionResponse;
+use crate::apis::dto::transactions_in_block_chain_response::TransactionsInBlockChainResponse;
+use crate::apis::dto::wallet::WalletListResponse;
+use crate::apis::dto::wallet_request::{
+    PasswordChangeRequest, WalletRequest, WalletTransferRequest,
+};
 use crate::apis::dto::Transaction as APITransaction;
 use crate::apis::dto::Wallet;
-use crate::core::blockchain::BlockChain;
+use crate::core::blockchain::balance::AccountState;
+use crate::core::blockchain::{BlockChain, BlockSearch, BlockSearchResult, TransactionRecord};
+use crate::core::miner::auto::{AutoMiner, MiningMode};
+use crate::core::miner::{Miner, MiningError};
+use crate::core::wallet::address::Address;
+use crate::core::wallet::hd::{HdWallet, GAP_LIMIT};
+use crate::core::wallet::keystore::{Keystore, KeystoreError};
+use crate::core::wallet::scheme::Scheme;
 use crate::core::wallet::Wallet as BlockchainWallet;
 use actix_web::web;
-use actix_web::{get, post, HttpResponse, Responder};
+use actix_web::{get, post, put, HttpResponse, Responder};
 use log::info;
+use std::ops::Bound;
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::RwLock;
 
-/// Wallet response schema
+/// Creates a wallet and stores it in the keystore of the node, encrypted with a password
 #[utoipa::path(
-    get,
+    post,
     path = "/wallet",
+    request_body = WalletRequest,
+    responses(
+        (status = 201, description = "Wallet created and stored", body = Wallet),
+        (status = 400, description = "Password is missing or the scheme is unknown"),
+        (status = 500, description = "Wallet could not be stored")
+    )
+)]
+#[post("/wallet")]
+pub async fn create_wallet(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    keystore: web::Data<Arc<Keystore>>,
+    request: web::Json<WalletRequest>,
+) -> HttpResponse {
+    let request = request.into_inner();
+    let Some(password) = request.password.filter(|p| !p.is_empty()) else {
+        return HttpResponse::BadRequest().body("Password is missing");
+    };
+    let scheme = match request.scheme.as_deref().map(str::parse::<Scheme>) {
+        Some(Ok(scheme)) => scheme,
+        Some(Err(e)) => return HttpResponse::BadRequest().body(e),
+        None => Scheme::default(),
+    };
+
+    // deriving the key is slow on purpose, keep it off the worker
+    let network = data.read().unwrap().config.network;
+    let keystore = keystore.get_ref().clone();
+    let created = web::block(move || {
+        let wallet = BlockchainWallet::generate(scheme, network);
+        wallet.save(&keystore, &password).map(|_| wallet)
+    })
+    .await;
+    match created {
+        Ok(Ok(wallet)) => HttpResponse::Created().json(Wallet::new_from(
+            &wallet.get_address(),
+            &wallet.public_key_str(),
+        )),
+        Ok(Err(e)) => keystore_error(e),
+        Err(_) => HttpResponse::InternalServerError().body("Wallet could not be stored"),
+    }
+}
+
+/// Addresses of the wallets in the keystore of the node
+#[utoipa::path(
+    get,
+    path = "/wallets",
+    responses(
+        (status = 200, description = "Stored wallets", body = WalletListResponse),
+        (status = 500, description = "Keystore is unreadable")
+    )
+)]
+#[get("/wallets")]
+pub async fn list_wallets(keystore: web::Data<Arc<Keystore>>) -> HttpResponse {
+    match keystore.list() {
+        Ok(addresses) => HttpResponse::Ok().json(WalletListResponse {
+            count: Some(addresses.len()),
+            addresses: Some(addresses),
+        }),
+        Err(e) => keystore_error(e.into()),
+    }
+}
+
+/// Address and public key of a stored wallet
+#[utoipa::path(
+    get,
+    path = "/wallet/{address}",
+    params(
+        ("address" = String, Path, description = "Address of the wallet")
+    ),
+    responses(
+        (status = 200, description = "Wallet found", body = Wallet),
+        (status = 404, description = "Wallet is not in the keystore")
+    )
+)]
+#[get("/wallet/{address}")]
+pub async fn get_wallet(
+    keystore: web::Data<Arc<Keystore>>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match keystore.read(&path.into_inner()) {
+        Ok(key) => HttpResponse::Ok().json(Wallet::new_from(&key.address, &key.public_key)),
+        Err(e) => keystore_error(e),
+    }
+}
+
+/// Encrypted keystore file of a stored wallet, to back it up or move it to another node
+#[utoipa::path(
+    get,
+    path = "/wallet/{address}/keystore",
+    params(
+        ("address" = String, Path, description = "Address of the wallet")
+    ),
+    responses(
+        (status = 200, description = "The keystore file, its private key encrypted"),
+        (status = 404, description = "Wallet is not in the keystore")
+    )
+)]
+#[get("/wallet/{address}/keystore")]
+pub async fn export_wallet(
+    keystore: web::Data<Arc<Keystore>>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match keystore.read(&path.into_inner()) {
+        Ok(key) => HttpResponse::Ok().json(key),
+        Err(e) => keystore_error(e),
+    }
+}
+
+/// Encrypts a stored wallet with a new password
+#[utoipa::path(
+    put,
+    path = "/wallet/{address}/password",
+    params(
+        ("address" = String, Path, description = "Address of the wallet")
+    ),
+    request_body = PasswordChangeRequest,
+    responses(
+        (status = 200, description = "Password changed"),
+        (status = 400, description = "A password is missing"),
+        (status = 401, description = "Wrong password"),
+        (status = 404, description = "Wallet is not in the keystore")
+    )
+)]
+#[put("/wallet/{address}/password")]
+pub async fn change_wallet_password(
+    keystore: web::Data<Arc<Keystore>>,
+    path: web::Path<String>,
+    request: web::Json<PasswordChangeRequest>,
+) -> HttpResponse {
+    let request = request.into_inner();
+    let (Some(password), Some(new_password)) = (
+        request.password,
+        request.new_password.filter(|p| !p.is_empty()),
+    ) else {
+        return HttpResponse::BadRequest().body("A password is missing");
+    };
+
+    let keystore = keystore.get_ref().clone();
+    let address = path.into_inner();
+    match web::block(move || keystore.change_password(&address, &password, &new_password)).await {
+        Ok(Ok(())) => HttpResponse::Ok().body("Password changed"),
+        Ok(Err(e)) => keystore_error(e),
+        Err(_) => HttpResponse::InternalServerError().body("Password could not be changed"),
+    }
+}
+
+/// Signs a transfer with a stored wallet and adds it to the blockchain
+#[utoipa::path(
+    post,
+    path = "/wallet/{address}/transaction",
+    params(
+        ("address" = String, Path, description = "Address of the sending wallet")
+    ),
+    request_body = WalletTransferRequest,
+    responses(
+        (status = 200, description = "Transaction added successfully"),
+        (status = 400, description = "Transaction is incomplete, its recipient invalid or rejected by the blockchain"),
+        (status = 401, description = "Wrong password"),
+        (status = 404, description = "Wallet is not in the keystore")
+    )
+)]
+#[post("/wallet/{address}/transaction")]
+pub async fn post_wallet_transaction(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    keystore: web::Data<Arc<Keystore>>,
+    path: web::Path<String>,
+    request: web::Json<WalletTransferRequest>,
+) -> HttpResponse {
+    let request = request.into_inner();
+    let (Some(password), Some(recipient), Some(amount)) =
+        (request.password, request.recipient, request.amount)
+    else {
+        return HttpResponse::BadRequest().body("Password, recipient or amount is missing");
+    };
+    if let Err(response) = check_address(&data, &recipient, "Recipient") {
+        return response;
+    }
+
+    let keystore = keystore.get_ref().clone();
+    let address = path.into_inner();
+    let wallet = match web::block(move || BlockchainWallet::load(&keystore, &address, &password))
+        .await
+    {
+        Ok(Ok(wallet)) => wallet,
+        Ok(Err(e)) => return keystore_error(e),
+        Err(_) => return HttpResponse::InternalServerError().body("Wallet could not be opened"),
+    };
+
+    let mut blockchain = data.write().unwrap();
+    let nonce = blockchain.next_nonce(&wallet.get_address());
+    let wallet_tx =
+        wallet.sign_transaction(&recipient, amount, request.fee.unwrap_or_default(), nonce);
+    if !blockchain.add_transaction(&wallet_tx) {
+        info!("Blockchain rejected the wallet transaction");
+        return HttpResponse::BadRequest().body("Transaction rejected by the blockchain");
+    }
+
+    info!("Wallet transaction added successfully to blockchain");
+    HttpResponse::Ok().body("Transaction added successfully")
+}
+
+/// Generates a mnemonic and stores the first wallets derived from it, encrypted with a password
+///
+/// The one place a secret leaves the node unencrypted: the mnemonic is not stored, so it is
+/// returned in plaintext, once, as the only backup of the wallets. Call it only over a
+/// connection nobody else can read.
+#[utoipa::path(
+    post,
+    path = "/wallet/hd",
+    request_body = HdWalletRequest,
     responses(
-        (status = 200, description = "Wallet information retrieved successfully", body = Wallet)
+        (status = 201, description = "Wallets created and stored, with the mnemonic backing them up in plaintext", body = HdWalletResponse),
+        (status = 400, description = "Password is missing or the word count or address count is invalid"),
+        (status = 500, description = "Wallets could not be stored")
     )
 )]
-#[get("/wallet")]
-async fn get_wallet_data() -> impl Responder {
-    // Create a new blockchain wallet instance
-    let blockchain_wallet = BlockchainWallet::new();
+#[post("/wallet/hd")]
+pub async fn create_hd_wallet(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    keystore: web::Data<Arc<Keystore>>,
+    request: web::Json<HdWalletRequest>,
+) -> HttpResponse {
+    let request = request.into_inner();
+    let Some(password) = request.password.filter(|p| !p.is_empty()) else {
+        return HttpResponse::BadRequest().body("Password is missing");
+    };
+    let count = request.count.unwrap_or(1);
+    if !(1..=HdWalletRequest::MAX_COUNT).contains(&count) {
+        return HttpResponse::BadRequest().body(format!(
+            "Count must be between 1 and {}",
+            HdWalletRequest::MAX_COUNT
+        ));
+    }
+    let mnemonic = match HdWallet::generate_mnemonic(request.words.unwrap_or(12)) {
+        Ok(mnemonic) => mnemonic.to_string(),
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let network = data.read().unwrap().config.network;
+    let keystore = keystore.get_ref().clone();
+    let passphrase = request.passphrase.unwrap_or_default();
+    let phrase = mnemonic.clone();
+    let stored = web::block(move || {
+        let hd_wallet = HdWallet::from_mnemonic(&phrase, &passphrase, network)
+            .expect("a generated mnemonic is valid");
+        hd_wallet
+            .wallets(0)
+            .take(count as usize)
+            .map(|(index, wallet)| {
+                wallet.save(&keystore, &password)?;
+                Ok(DerivedAddress::from_account(
+                    index,
+                    &wallet.get_address(),
+                    AccountState::default(),
+                ))
+            })
+            .collect::<Result<Vec<_>, KeystoreError>>()
+    })
+    .await;
+    match stored {
+        Ok(Ok(addresses)) => HttpResponse::Created().json(HdWalletResponse {
+            mnemonic: Some(mnemonic),
+            addresses: Some(addresses),
+        }),
+        Ok(Err(e)) => keystore_error(e),
+        Err(_) => HttpResponse::InternalServerError().body("Wallets could not be stored"),
+    }
+}
+
+/// Restores the wallets of a mnemonic: scans the chain for the addresses derived from it
+/// that were used and stores them with the next unused one, encrypted with a password
+#[utoipa::path(
+    post,
+    path = "/wallet/hd/recover",
+    request_body = HdRecoverRequest,
+    responses(
+        (status = 200, description = "Wallets restored and stored", body = HdWalletResponse),
+        (status = 400, description = "Mnemonic or password is missing or invalid"),
+        (status = 500, description = "Wallets could not be stored")
+    )
+)]
+#[post("/wallet/hd/recover")]
+pub async fn recover_hd_wallet(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    keystore: web::Data<Arc<Keystore>>,
+    request: web::Json<HdRecoverRequest>,
+) -> HttpResponse {
+    let request = request.into_inner();
+    let (Some(mnemonic), Some(password)) =
+        (request.mnemonic, request.password.filter(|p| !p.is_empty()))
+    else {
+        return HttpResponse::BadRequest().body("Mnemonic or password is missing");
+    };
+    let gap_limit = request.gap_limit.unwrap_or(GAP_LIMIT);
+    if !(1..=HdRecoverRequest::MAX_GAP_LIMIT).contains(&gap_limit) {
+        return HttpResponse::BadRequest().body(format!(
+            "Gap limit must be between 1 and {}",
+            HdRecoverRequest::MAX_GAP_LIMIT
+        ));
+    }
+    let network = data.read().unwrap().config.network;
+    let passphrase = request.passphrase.unwrap_or_default();
+    let hd_wallet = match HdWallet::from_mnemonic(&mnemonic, &passphrase, network) {
+        Ok(hd_wallet) => hd_wallet,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let mut restored = {
+        let blockchain = data.read().unwrap();
+        hd_wallet
+            .scan(&blockchain, gap_limit)
+            .into_iter()
+            .map(|(index, wallet)| {
+                let account = blockchain.account(&wallet.get_address());
+                (index, wallet, account)
+            })
+            .collect::<Vec<_>>()
+    };
+    // the next address to hand out, so payments to it are found by the next scan
+    let next = restored.last().map_or(0, |(index, _, _)| index + 1);
+    restored.push((next, hd_wallet.wallet(next), AccountState::default()));
 
-    // Map the blockchain wallet to the API wallet structure
-    let api_wallet = Wallet::new_from(
-        &blockchain_wallet.get_address(),
-        &blockchain_wallet.public_key_str(),
-        &blockchain_wallet.private_key_str(),
-    );
+    let keystore = keystore.get_ref().clone();
+    let stored = web::block(move || {
+        restored
+            .into_iter()
+            .map(|(index, wallet, account)| {
+                wallet.save(&keystore, &password)?;
+                Ok(DerivedAddress::from_account(
+                    index,
+                    &wallet.get_address(),
+                    account,
+                ))
+            })
+            .collect::<Result<Vec<_>, KeystoreError>>()
+    })
+    .await;
+    match stored {
+        Ok(Ok(addresses)) => HttpResponse::Ok().json(HdWalletResponse {
+            mnemonic: None,
+            addresses: Some(addresses),
+        }),
+        Ok(Err(e)) => keystore_error(e),
+        Err(_) => HttpResponse::InternalServerError().body("Wallets could not be stored"),
+    }
+}
 
-    HttpResponse::Ok().json(api_wallet)
+/// 400 unless `address` is well formed and on the network of the chain, `name` telling
+/// which address is wrong.
+fn check_address(
+    data: &RwLock<BlockChain>,
+    address: &str,
+    name: &str,
+) -> Result<Address, HttpResponse> {
+    let network = data.read().unwrap().config.network;
+    Address::validate(address, network)
+        .map_err(|e| HttpResponse::BadRequest().body(format!("{} is invalid: {}", name, e)))
 }
 
-/// Handle transactions by adding them to the blockchain
+fn keystore_error(e: KeystoreError) -> HttpResponse {
+    match e {
+        KeystoreError::NotFound => HttpResponse::NotFound().body(e.to_string()),
+        KeystoreError::WrongPassword => HttpResponse::Unauthorized().body(e.to_string()),
+        KeystoreError::Io(_) | KeystoreError::Malformed(_) => {
+            HttpResponse::InternalServerError().body(e.to_string())
+        }
+    }
+}
+
+/// Handle transactions by adding them to the blockchain, signing them with the private
+/// key sent by the client. Prefer `/transaction/signed`, which never sees the key.
 #[utoipa::path(
     post,
     path = "/transaction",
     request_body = APITransaction,
     responses(
         (status = 200, description = "Transaction added successfully"),
-        (status = 500, description = "Failed to add transaction to blockchain")
+        (status = 400, description = "Amount is missing, the scheme unknown, the private key or the recipient invalid, or the transaction rejected by the blockchain")
     )
 )]
 #[post("/transaction")]
 pub async fn get_transaction_handler(
-    data: web::Data<Arc<Mutex<BlockChain>>>,
+    data: web::Data<Arc<RwLock<BlockChain>>>,
     transaction: web::Json<APITransaction>,
 ) -> impl Responder {
     let tx = transaction.into_inner();
@@ -54,102 +429,525 @@ pub async fn get_transaction_handler(
         Some(value) => value,
         None => return HttpResponse::BadRequest().body("Amount is missing"),
     };
+    let recipient = tx.recipient.clone().unwrap_or_default();
+    if let Err(response) = check_address(&data, &recipient, "Recipient") {
+        return response;
+    }
+
+    let scheme = match tx.scheme.as_deref().map(str::parse::<Scheme>) {
+        Some(Ok(scheme)) => scheme,
+        Some(Err(e)) => return HttpResponse::BadRequest().body(e),
+        None => Scheme::default(),
+    };
 
     // Create wallet instance from transaction details
-    let wallet = BlockchainWallet::new_from(
-        &tx.public_key.clone().unwrap_or_default(),
+    let Some(wallet) = BlockchainWallet::new_from(
+        scheme,
         &tx.private_key.clone().unwrap_or_default(),
         &tx.sender.clone().unwrap_or_default(),
-    );
+    ) else {
+        return HttpResponse::BadRequest().body("Private key is invalid");
+    };
 
-    // Sign the transaction
+    // Access the blockchain from the shared state
+    let mut blockchain = data.write().unwrap();
+
+    // Sign the transaction with the next nonce of the sender
     let amount: u64 = amount as u64; // Convert f64 to u64
-    let wallet_tx = wallet.sign_transaction(&tx.recipient.clone().unwrap_or_default(), amount);
+    let fee: u64 = tx.fee.unwrap_or_default() as u64;
+    let nonce = blockchain.next_nonce(&wallet.get_address());
+    let wallet_tx = wallet.sign_transaction(&recipient, amount, fee, nonce);
 
-    // Access the blockchain from the shared state
-    let mut blockchain = data.lock().unwrap();
     let add_result = blockchain.add_transaction(&wallet_tx);
 
+    // a transfer the chain turns down, unfunded or badly signed, is the client's to fix
     if !add_result {
-        info!("Failed to add transaction to blockchain");
-        return HttpResponse::InternalServerError().body("Failed to add transaction to blockchain");
+        info!("Blockchain rejected the transaction");
+        return HttpResponse::BadRequest().body("Transaction rejected by the blockchain");
     }
 
     info!("Transaction added successfully to blockchain");
     HttpResponse::Ok().body("Transaction added successfully")
 }
 
+/// Add a transaction already signed by the wallet of the sender
+#[utoipa::path(
+    post,
+    path = "/transaction/signed",
+    request_body = SignedTransaction,
+    responses(
+        (status = 200, description = "Transaction added successfully"),
+        (status = 400, description = "Transaction is incomplete, badly signed, its sender or recipient invalid or rejected by the blockchain")
+    )
+)]
+#[post("/transaction/signed")]
+pub async fn post_signed_transaction(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    transaction: web::Json<SignedTransaction>,
+) -> impl Responder {
+    let Some(wallet_tx) = transaction.to_wallet_transaction() else {
+        return HttpResponse::BadRequest()
+            .body("Transaction is missing fields or its scheme is unknown");
+    };
+    if let Err(response) = check_address(&data, &wallet_tx.recipient, "Recipient") {
+        return response;
+    }
+    let sender = match check_address(&data, &wallet_tx.sender, "Sender") {
+        Ok(sender) => sender,
+        Err(response) => return response,
+    };
+
+    if !BlockchainWallet::verify_transaction(&wallet_tx, sender.network()) {
+        return HttpResponse::BadRequest().body("Signature does not match the transaction");
+    }
+
+    // anyone can sign with their own key, it must also be the key of the sender
+    if !BlockchainWallet::is_signed_by_sender(&wallet_tx) {
+        return HttpResponse::BadRequest().body("Public key does not belong to the sender");
+    }
+
+    let mut blockchain = data.write().unwrap();
+    if !blockchain.add_transaction(&wallet_tx) {
+        info!("Blockchain rejected the signed transaction");
+        return HttpResponse::BadRequest().body("Transaction rejected by the blockchain");
+    }
+
+    info!("Signed transaction added successfully to blockchain");
+    HttpResponse::Ok().body("Transaction added successfully")
+}
+
+/// Transaction lookup by txid, in the chain or still pending
+#[utoipa::path(
+    get,
+    path = "/transaction/{txid}",
+    params(
+        ("txid" = String, Path, description = "Hex of the transaction id")
+    ),
+    responses(
+        (status = 200, description = "Transaction found", body = TransactionResponse),
+        (status = 400, description = "Txid is not hex"),
+        (status = 404, description = "No transaction with this txid")
+    )
+)]
+#[get("/transaction/{txid}")]
+pub async fn get_transaction(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let Ok(txid) = hex::decode(path.into_inner()) else {
+        return HttpResponse::BadRequest().body("Txid is not hex");
+    };
+
+    let blockchain = data.read().unwrap();
+    match blockchain.find_transaction(&txid) {
+        Some(record) => HttpResponse::Ok().json(TransactionResponse::from_record(&record)),
+        None => HttpResponse::NotFound().body("Transaction not found"),
+    }
+}
+
+/// Pending transactions with their fees
+#[utoipa::path(
+    get,
+    path = "/mempool",
+    responses(
+        (status = 200, description = "Transactions waiting for a block, the best paying per byte first", body = MempoolResponse)
+    )
+)]
+#[get("/mempool")]
+pub async fn get_mempool(data: web::Data<Arc<RwLock<BlockChain>>>) -> HttpResponse {
+    let blockchain = data.read().unwrap();
+    HttpResponse::Ok().json(MempoolResponse::from_mempool(blockchain.mempool()))
+}
+
 /// Amount retrieval handler
 #[utoipa::path(
     get,
     path = "/amount/{address}",
+    params(
+        ("address" = String, Path, description = "Address to get the balance of"),
+        ("height" = Option<usize>, Query, description = "Block height to get the balance at, the tip when missing")
+    ),
     responses(
-        (status = 200, description = "Amount retrieved successfully", body = crate::apis::dto::query_amount::QueryAmount)
+        (status = 200, description = "Amount retrieved successfully", body = QueryAmountResponse),
+        (status = 400, description = "Height is past the tip of the chain")
     )
 )]
 #[get("/amount/{address}")]
 pub async fn get_amount(
-    data: web::Data<Arc<Mutex<BlockChain>>>,
+    data: web::Data<Arc<RwLock<BlockChain>>>,
     path: web::Path<String>,
+    query: web::Query<HeightQuery>,
 ) -> HttpResponse {
     let address = path.into_inner();
 
-    // Logic to get the amount for the given address
-    let blockchain = data.lock().unwrap();
-    let amount = blockchain.calculate_total_amount(address); // Assuming this method exists in BlockChain
+    // Balances are indexed per height, no need to replay the chain
+    let blockchain = data.read().unwrap();
+    let height = query.height.unwrap_or(blockchain.len() - 1);
+    let Some(account) = blockchain.account_at(&address, height) else {
+        return HttpResponse::BadRequest().body("Height is past the tip of the chain");
+    };
 
     let response = QueryAmountResponse {
-        amount: Some(amount as f32),
+        amount: Some(account.balance),
+        nonce: Some(account.nonce),
+        // pending transactions only exist at the tip
+        next_nonce: (height == blockchain.len() - 1).then(|| blockchain.next_nonce(&address)),
+        transaction_count: Some(account.tx_count),
+        height: Some(height),
     };
     HttpResponse::Ok().json(response)
 }
 
-/// Mining process handler
+/// Mining process handler, the chain stays available while the block is searched
 #[utoipa::path(
     get,
     path = "/mining",
     responses(
-        (status = 200, description = "Mining started successfully")
+        (status = 200, description = "Block mined and added to the chain", body = MiningResponse),
+        (status = 409, description = "Another block is being mined, or a new block arrived first"),
+        (status = 500, description = "Mining failed")
     )
 )]
 #[get("/mining")]
-pub async fn mining(data: web::Data<Arc<Mutex<BlockChain>>>) -> impl Responder {
-    let mut blockchain = data.lock().unwrap();
-    let is_mined = blockchain.mining();
-    if !is_mined {
-        return HttpResponse::InternalServerError().body("Mining failed");
+pub async fn mining(miner: web::Data<Arc<Miner>>) -> HttpResponse {
+    let miner = miner.get_ref().clone();
+    match web::block(move || miner.mine()).await {
+        Ok(Ok(mined)) => HttpResponse::Ok().json(MiningResponse::from_mined(&mined)),
+        Ok(Err(e @ (MiningError::Busy | MiningError::Cancelled))) => {
+            HttpResponse::Conflict().body(e.to_string())
+        }
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(_) => HttpResponse::InternalServerError().body("Mining failed"),
+    }
+}
+
+/// Starts mining in the background, continuously, every few seconds or once enough
+/// transactions are pending
+#[utoipa::path(
+    post,
+    path = "/mining/start",
+    request_body = MiningStartRequest,
+    responses(
+        (status = 200, description = "Background mining started", body = MiningStatusResponse),
+        (status = 400, description = "Unknown mining mode or invalid miner address"),
+        (status = 409, description = "Background mining is already running")
+    )
+)]
+#[post("/mining/start")]
+pub async fn start_mining(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    auto_miner: web::Data<Arc<AutoMiner>>,
+    request: Option<web::Json<MiningStartRequest>>,
+) -> HttpResponse {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+    let mode = match request.mode {
+        Some(mode) => match mode.parse::<MiningMode>() {
+            Ok(mode) => mode,
+            Err(e) => return HttpResponse::BadRequest().body(e),
+        },
+        None => data
+            .read()
+            .unwrap()
+            .config
+            .mining_mode
+            .unwrap_or(MiningMode::Continuous),
+    };
+    if let Some(address) = &request.miner_address {
+        if let Err(response) = check_address(&data, address, "Miner address") {
+            return response;
+        }
+    }
+    if auto_miner.mode().is_some() {
+        return HttpResponse::Conflict().body("Background mining is already running");
+    }
+
+    if let Some(address) = request.miner_address {
+        data.write().unwrap().set_reward_address(address);
     }
-    HttpResponse::Ok().body("Mining started successfully")
+    if !auto_miner.start(mode) {
+        return HttpResponse::Conflict().body("Background mining is already running");
+    }
+    mining_status(&data, &auto_miner)
 }
 
-/// Show transactions handler
+/// Stops the background mining and abandons the block being searched
+#[utoipa::path(
+    post,
+    path = "/mining/stop",
+    responses(
+        (status = 200, description = "Background mining stopped", body = MiningStatusResponse),
+        (status = 409, description = "Background mining is not running")
+    )
+)]
+#[post("/mining/stop")]
+pub async fn stop_mining(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    auto_miner: web::Data<Arc<AutoMiner>>,
+) -> HttpResponse {
+    if !auto_miner.stop() {
+        return HttpResponse::Conflict().body("Background mining is not running");
+    }
+    mining_status(&data, &auto_miner)
+}
+
+/// State of the background mining
+#[utoipa::path(
+    get,
+    path = "/mining/status",
+    responses(
+        (status = 200, description = "Background mining state", body = MiningStatusResponse)
+    )
+)]
+#[get("/mining/status")]
+pub async fn get_mining_status(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    auto_miner: web::Data<Arc<AutoMiner>>,
+) -> HttpResponse {
+    mining_status(&data, &auto_miner)
+}
+
+fn mining_status(data: &Arc<RwLock<BlockChain>>, auto_miner: &AutoMiner) -> HttpResponse {
+    let address = data.read().unwrap().reward_address().to_string();
+    HttpResponse::Ok().json(MiningStatusResponse::from_auto_miner(auto_miner, &address))
+}
+
+/// Transactions of the main chain, the newest first
 #[utoipa::path(
     get,
     path = "/show_transactions",
+    params(
+        ("offset" = Option<usize>, Query, description = "Number of transactions to skip"),
+        ("limit" = Option<usize>, Query, description = "Most transactions to return, 20 by default and at most 100")
+    ),
     responses(
-        (status = 200, description = "Transactions shown successfully", body = crate::apis::dto::transactions_in_block_chain_response::TransactionsInBlockChainResponse)
+        (status = 200, description = "Transactions shown successfully", body = TransactionsInBlockChainResponse)
     )
 )]
 #[get("/show_transactions")]
-pub async fn show_transaction(_data: web::Data<Arc<Mutex<BlockChain>>>) -> HttpResponse {
-    // Simulating a response, you can implement the actual logic here
-    // let blockchain = data.lock().unwrap();
-    // let transactions = blockchain.search_block();  // Assuming this method exists in BlockChain
-    // let transaction_count = transactions.len();
-    //
-    // let response = TransactionsInBlockChainResponse {
-    //     transaction_count,
-    //     transactions,
-    // };
+pub async fn show_transaction(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    query: web::Query<PageQuery>,
+) -> HttpResponse {
+    let blockchain = data.read().unwrap();
+    let records: Vec<TransactionRecord> = (0..blockchain.len())
+        .rev()
+        .flat_map(|height| blockchain.block_transactions(height).into_iter().rev())
+        .skip(query.offset())
+        .take(query.limit())
+        .collect();
+
+    HttpResponse::Ok().json(TransactionsInBlockChainResponse::from_records(
+        blockchain.transaction_count(),
+        &records,
+    ))
+}
+
+/// Blocks of the main chain, the tip first, optionally within heights and times
+#[utoipa::path(
+    get,
+    path = "/blocks",
+    params(
+        ("offset" = Option<usize>, Query, description = "Number of blocks to skip from the newest"),
+        ("limit" = Option<usize>, Query, description = "Most blocks to return, 20 by default and at most 100"),
+        ("fromHeight" = Option<usize>, Query, description = "Lowest height to list"),
+        ("toHeight" = Option<usize>, Query, description = "Highest height to list"),
+        ("since" = Option<u64>, Query, description = "Earliest time stamp to list, unix nanoseconds"),
+        ("until" = Option<u64>, Query, description = "Latest time stamp to list, unix nanoseconds")
+    ),
+    responses(
+        (status = 200, description = "A page of blocks without their transactions", body = BlockListResponse)
+    )
+)]
+#[get("/blocks")]
+pub async fn get_blocks(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    query: web::Query<PageQuery>,
+    range: web::Query<BlockRangeQuery>,
+) -> HttpResponse {
+    let blockchain = data.read().unwrap();
+    let (by_height, in_heights) = blockchain
+        .blocks_by_height(range.from_height.unwrap_or(0)..=range.to_height.unwrap_or(usize::MAX));
+    let (by_time, in_window) = blockchain.blocks_by_time((
+        range
+            .since
+            .map_or(Bound::Unbounded, |t| Bound::Included(t as u128)),
+        range
+            .until
+            .map_or(Bound::Unbounded, |t| Bound::Included(t as u128)),
+    ));
+    let start = by_height.max(by_time);
+    let end = (by_height + in_heights.len()).min(by_time + in_window.len());
+
+    let blocks = (start..end.max(start))
+        .rev()
+        .skip(query.offset())
+        .take(query.limit())
+        .map(|height| BlockResponse::from_block(height, &blockchain[height], blockchain.len()))
+        .collect();
+
+    HttpResponse::Ok().json(BlockListResponse {
+        height: Some(blockchain.len() - 1),
+        count: Some(end.saturating_sub(start)),
+        offset: Some(query.offset()),
+        limit: Some(query.limit()),
+        blocks: Some(blocks),
+    })
+}
+
+/// Block of the main chain at a height, with its transactions
+#[utoipa::path(
+    get,
+    path = "/block/{height}",
+    params(
+        ("height" = usize, Path, description = "Height of the block")
+    ),
+    responses(
+        (status = 200, description = "Block found", body = BlockResponse),
+        (status = 404, description = "No block at this height")
+    )
+)]
+#[get("/block/{height}")]
+pub async fn get_block(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    path: web::Path<usize>,
+) -> HttpResponse {
+    let blockchain = data.read().unwrap();
+    block_response(&blockchain, BlockSearch::SearchByIndex(path.into_inner()))
+}
+
+/// Block of the main chain with a hash, with its transactions
+#[utoipa::path(
+    get,
+    path = "/block/hash/{hash}",
+    params(
+        ("hash" = String, Path, description = "Hex of the block hash")
+    ),
+    responses(
+        (status = 200, description = "Block found", body = BlockResponse),
+        (status = 400, description = "Hash is not hex"),
+        (status = 404, description = "No main chain block with this hash")
+    )
+)]
+#[get("/block/hash/{hash}")]
+pub async fn get_block_by_hash(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let Ok(hash) = hex::decode(path.into_inner()) else {
+        return HttpResponse::BadRequest().body("Hash is not hex");
+    };
+
+    let blockchain = data.read().unwrap();
+    block_response(&blockchain, BlockSearch::SearchByBlockHash(hash))
+}
+
+fn block_response(blockchain: &BlockChain, search: BlockSearch) -> HttpResponse {
+    match blockchain.search_block(search) {
+        BlockSearchResult::Success(height, block) => HttpResponse::Ok().json(
+            BlockResponse::from_block(height, block, blockchain.len())
+                .with_transactions(&blockchain.block_transactions(height)),
+        ),
+        _ => HttpResponse::NotFound().body("Block not found"),
+    }
+}
+
+/// Transactions of the main chain block at a height
+#[utoipa::path(
+    get,
+    path = "/block/{height}/transactions",
+    params(
+        ("height" = usize, Path, description = "Height of the block")
+    ),
+    responses(
+        (status = 200, description = "Transactions of the block in block order", body = TransactionsInBlockChainResponse),
+        (status = 404, description = "No block at this height")
+    )
+)]
+#[get("/block/{height}/transactions")]
+pub async fn get_block_transactions(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    path: web::Path<usize>,
+) -> HttpResponse {
+    let blockchain = data.read().unwrap();
+    match blockchain.search_block(BlockSearch::SearchByIndex(path.into_inner())) {
+        BlockSearchResult::Success(height, _) => {
+            let records = blockchain.block_transactions(height);
+            HttpResponse::Ok().json(TransactionsInBlockChainResponse::from_records(
+                records.len(),
+                &records,
+            ))
+        }
+        _ => HttpResponse::NotFound().body("Block not found"),
+    }
+}
+
+/// Transactions sent by or paying to an address, the pending ones first
+#[utoipa::path(
+    get,
+    path = "/address/{address}/transactions",
+    params(
+        ("address" = String, Path, description = "Address to get the history of"),
+        ("offset" = Option<usize>, Query, description = "Number of transactions to skip"),
+        ("limit" = Option<usize>, Query, description = "Most transactions to return, 20 by default and at most 100")
+    ),
+    responses(
+        (status = 200, description = "A page of the history of the address", body = AddressHistoryResponse)
+    )
+)]
+#[get("/address/{address}/transactions")]
+pub async fn get_address_history(
+    data: web::Data<Arc<RwLock<BlockChain>>>,
+    path: web::Path<String>,
+    query: web::Query<PageQuery>,
+) -> HttpResponse {
+    let address = path.into_inner();
+
+    let blockchain = data.read().unwrap();
+    let account = blockchain.account(&address);
+    let records: Vec<TransactionRecord> = blockchain
+        .address_history(&address)
+        .skip(query.offset())
+        .take(query.limit())
+        .collect();
 
-    HttpResponse::Ok().json("")
+    HttpResponse::Ok().json(AddressHistoryResponse {
+        balance: Some(account.balance),
+        transaction_count: Some(account.tx_count),
+        offset: Some(query.offset()),
+        limit: Some(query.limit()),
+        transactions: Some(
+            records
+                .iter()
+                .map(TransactionResponse::from_record)
+                .collect(),
+        ),
+        address: Some(address),
+    })
 }
 
 /// Configure the `wallet_api` routes
 pub fn configure(cfg: &mut actix_web::web::ServiceConfig) {
-    cfg.service(get_wallet_data);
+    cfg.service(create_wallet);
+    cfg.service(list_wallets);
+    cfg.service(get_wallet);
+    cfg.service(export_wallet);
+    cfg.service(change_wallet_password);
+    cfg.service(post_wallet_transaction);
+    cfg.service(create_hd_wallet);
+    cfg.service(recover_hd_wallet);
     cfg.service(get_transaction_handler);
+    cfg.service(post_signed_transaction);
+    cfg.service(get_transaction);
+    cfg.service(get_mempool);
     cfg.service(get_amount);
     cfg.service(mining);
+    cfg.service(start_mining);
+    cfg.service(stop_mining);
+    cfg.service(get_mining_status);
     cfg.service(show_transaction);
+    cfg.service(get_blocks);
+    cfg.service(get_block_by_hash);
+    cfg.service(get_block_transactions);
+    cfg.service(get_block);
+    cfg.service(get_address_history);
 }
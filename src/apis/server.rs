@@ -1,36 +1,153 @@
 use crate::apis;
+use crate::config::Config;
+use crate::core::blockchain::BlockChain;
+use crate::core::miner::auto::AutoMiner;
+use crate::core::miner::Miner;
+use crate::core::wallet::address::Address;
+use crate::core::wallet::keystore::Keystore;
+use crate::core::wallet::Wallet;
 use actix_files as fs;
-use actix_web::{App, HttpServer};
+use actix_web::{web, App, HttpServer};
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::time;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+/// How often expired pending transactions are dropped.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Define the OpenAPI documentation
 #[derive(OpenApi)]
 #[openapi(
     paths(
-        apis::handler::get_wallet_data,
+        apis::handler::create_wallet,
+        apis::handler::list_wallets,
+        apis::handler::get_wallet,
+        apis::handler::export_wallet,
+        apis::handler::change_wallet_password,
+        apis::handler::post_wallet_transaction,
+        apis::handler::create_hd_wallet,
+        apis::handler::recover_hd_wallet,
         apis::handler::get_transaction_handler,
+        apis::handler::post_signed_transaction,
+        apis::handler::get_transaction,
+        apis::handler::get_mempool,
         apis::handler::get_amount,
         apis::handler::show_transaction,
-        apis::handler::mining
+        apis::handler::get_blocks,
+        apis::handler::get_block,
+        apis::handler::get_block_by_hash,
+        apis::handler::get_block_transactions,
+        apis::handler::get_address_history,
+        apis::handler::mining,
+        apis::handler::start_mining,
+        apis::handler::stop_mining,
+        apis::handler::get_mining_status
     ),
     components(schemas(
         apis::dto::Wallet,
+        apis::dto::wallet::WalletListResponse,
+        apis::dto::wallet_request::WalletRequest,
+        apis::dto::wallet_request::PasswordChangeRequest,
+        apis::dto::wallet_request::WalletTransferRequest,
+        apis::dto::hd_wallet_request::HdWalletRequest,
+        apis::dto::hd_wallet_request::HdRecoverRequest,
+        apis::dto::hd_wallet_response::HdWalletResponse,
+        apis::dto::hd_wallet_response::DerivedAddress,
         apis::dto::Transaction,
+        apis::dto::signed_transaction::SignedTransaction,
+        apis::dto::transaction_response::TransactionResponse,
+        apis::dto::transaction_response::TransactionInput,
+        apis::dto::transaction_response::TransactionOutput,
+        apis::dto::mempool_response::MempoolResponse,
+        apis::dto::mempool_response::PendingTransaction,
+        apis::dto::mining_response::MiningResponse,
+        apis::dto::mining_request::MiningStartRequest,
+        apis::dto::mining_response::MiningStatusResponse,
+        apis::dto::block_response::BlockResponse,
+        apis::dto::block_response::BlockListResponse,
+        apis::dto::address_response::AddressHistoryResponse,
         crate::apis::dto::query_amount::QueryAmount,
+        crate::apis::dto::query_amount_response::QueryAmountResponse,
         crate::apis::dto::transactions_in_block_chain_response::TransactionsInBlockChainResponse
     )),
     info(title = "Blockchain API", version = "1.0.0")
 )]
 pub struct ApiDoc;
 
-pub struct Server;
+/// Directory of the encrypted wallets, inside the data directory.
+const KEYSTORE_DIR: &str = "keystore";
+
+/// The HTTP API of a node, owning its chain and what mines it.
+pub struct Server {
+    config: Config,
+    blockchain: Arc<RwLock<BlockChain>>,
+    miner: Arc<Miner>,
+    auto_miner: Arc<AutoMiner>,
+    keystore: Arc<Keystore>,
+}
 
 impl Server {
-    /// Runs the server
-    pub async fn run() -> std::io::Result<()> {
+    /// Opens the chain of `config.data_dir`, paying the blocks this node mines to
+    /// `Config::miner_address`, or to `miner_wallet` when it is not set. Must be called
+    /// from within the runtime the server runs on.
+    pub fn new(config: Config, miner_wallet: &Wallet) -> io::Result<Self> {
+        if let Some(address) = &config.miner_address {
+            Address::validate(address, config.network).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("MINER_ADDRESS: {}", e))
+            })?;
+        }
+        let reward_address = config
+            .miner_address
+            .clone()
+            .unwrap_or_else(|| miner_wallet.get_address());
+        let blockchain = Arc::new(RwLock::new(BlockChain::new(
+            config.clone(),
+            reward_address,
+        )?));
+        let miner = Arc::new(Miner::new(blockchain.clone(), config.mining_threads));
+        let auto_miner = Arc::new(AutoMiner::new(miner.clone(), blockchain.clone()));
+        let keystore = Arc::new(Keystore::open(
+            Path::new(&config.data_dir).join(KEYSTORE_DIR),
+        )?);
+        Ok(Server {
+            config,
+            blockchain,
+            miner,
+            auto_miner,
+            keystore,
+        })
+    }
+
+    /// The chain served, to share with the rest of the node.
+    pub fn blockchain(&self) -> Arc<RwLock<BlockChain>> {
+        self.blockchain.clone()
+    }
+
+    /// Starts the configured background mining and the expiry of pending transactions,
+    /// and serves the API until shut down.
+    pub async fn run(self) -> io::Result<()> {
+        if let Some(mode) = self.config.mining_mode {
+            self.auto_miner.start(mode);
+        }
+        tokio::spawn(expire_periodically(self.blockchain.clone()));
+
+        let Server {
+            config,
+            blockchain,
+            miner,
+            auto_miner,
+            keystore,
+        } = self;
         HttpServer::new(move || {
             App::new()
+                .app_data(web::Data::new(blockchain.clone()))
+                .app_data(web::Data::new(miner.clone()))
+                .app_data(web::Data::new(auto_miner.clone()))
+                .app_data(web::Data::new(keystore.clone()))
                 // Serve the pre-defined YAML file at /api-docs/yaml
                 .service(
                     fs::Files::new("/api-docs/yaml", "./api") // Adjust path to your `service.yml`
@@ -44,8 +161,18 @@ impl Server {
                 // Configure your APIs
                 .configure(apis::handler::configure)
         })
-        .bind(("127.0.0.1", 8080))?
+        .bind(("127.0.0.1", config.http_port))?
         .run()
         .await
     }
 }
+
+/// Drops the expired pending transactions every `EXPIRY_INTERVAL`, also while no block is
+/// mined and no transaction arrives, so readers of the pool never need to.
+async fn expire_periodically(blockchain: Arc<RwLock<BlockChain>>) {
+    let mut interval = time::interval(EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        blockchain.write().unwrap().expire_transactions();
+    }
+}
@@ -1,16 +1,139 @@
-#[derive(Debug)]
+use crate::core::miner::auto::MiningMode;
+use crate::core::wallet::address::Network;
+use std::env;
+use std::str::FromStr;
+use std::thread;
+
+#[derive(Debug, Clone)]
 pub struct Config {
+    /// Leading zero hex digits required from the hash of the first blocks.
     pub difficulty: usize,
-    pub sender: &'static str,
+    /// Seconds a block should take to mine, the target is adjusted toward it.
+    pub target_block_time: u64,
+    /// Number of blocks between two target adjustments.
+    pub retarget_interval: usize,
+    /// Largest factor a single adjustment can change the target by.
+    pub max_adjustment: u64,
+    /// Coins created by the coinbase of a block before the first halving.
     pub reward: u64,
+    /// Number of blocks between two halvings of the reward.
+    pub halving_interval: usize,
+    /// Most coins the coinbases of the chain create together.
+    pub max_supply: u64,
+    /// Network the addresses of the chain belong to.
+    pub network: Network,
+    /// Largest serialized size of a block in bytes.
+    pub max_block_size: usize,
+    /// Most transactions in a block, its coinbase included.
+    pub max_block_transactions: usize,
+    /// Number of threads searching for the proof of work of a block.
+    pub mining_threads: usize,
+    /// How the node mines on its own from start, `None` to only mine on request.
+    pub mining_mode: Option<MiningMode>,
+    /// Address the coinbase of mined blocks pays to, a fresh one per start when `None`.
+    pub miner_address: Option<String>,
+    /// Most transactions waiting for a block.
+    pub mempool_max_transactions: usize,
+    /// Largest serialized size of the transactions waiting for a block in bytes.
+    pub mempool_max_size: usize,
+    /// Seconds a transaction may wait for a block before it is dropped.
+    pub mempool_expiry: u64,
+    pub data_dir: String,
+    pub http_port: u16,
+    pub p2p_port: u16,
+    pub peers: Vec<String>,
 }
 
-impl Config {
-    pub fn default() -> Self {
+impl Default for Config {
+    fn default() -> Self {
         Config {
             difficulty: 3,
-            sender: "THE BLOCKCHAIN",
+            target_block_time: 10,
+            retarget_interval: 10,
+            max_adjustment: 4,
             reward: 1,
+            halving_interval: 210_000,
+            max_supply: 21_000_000,
+            network: Network::Mainnet,
+            max_block_size: 1_000_000,
+            max_block_transactions: 1_000,
+            mining_threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            mining_mode: None,
+            miner_address: None,
+            mempool_max_transactions: 5_000,
+            mempool_max_size: 5_000_000,
+            mempool_expiry: 86_400,
+            data_dir: "data".to_string(),
+            http_port: 8080,
+            p2p_port: 6000,
+            peers: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the settings from the environment and `.env`, falling back to the defaults.
+    /// `PEERS` is a comma separated list of `host:port` P2P addresses, `MINING_MODE` is
+    /// `continuous`, `interval:<seconds>` or `mempool:<transactions>`, any other value is
+    /// reported and the node does not mine on its own, `NETWORK` is `mainnet` or `testnet`.
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        let default = Config::default();
+        Config {
+            difficulty: env_or("DIFFICULTY", default.difficulty),
+            target_block_time: env_or("TARGET_BLOCK_TIME", default.target_block_time),
+            retarget_interval: env_or("RETARGET_INTERVAL", default.retarget_interval),
+            max_adjustment: env_or("MAX_ADJUSTMENT", default.max_adjustment),
+            reward: env_or("REWARD", default.reward),
+            halving_interval: env_or("HALVING_INTERVAL", default.halving_interval),
+            max_supply: env_or("MAX_SUPPLY", default.max_supply),
+            network: env_or("NETWORK", default.network),
+            max_block_size: env_or("MAX_BLOCK_SIZE", default.max_block_size),
+            max_block_transactions: env_or(
+                "MAX_BLOCK_TRANSACTIONS",
+                default.max_block_transactions,
+            ),
+            mining_threads: env_or("MINING_THREADS", default.mining_threads),
+            mining_mode: env::var("MINING_MODE")
+                .ok()
+                .filter(|mode| !mode.trim().is_empty())
+                .and_then(|mode| match mode.parse() {
+                    Ok(mode) => Some(mode),
+                    Err(e) => {
+                        println!("MINING_MODE is ignored, {}", e);
+                        None
+                    }
+                })
+                .or(default.mining_mode),
+            miner_address: env::var("MINER_ADDRESS")
+                .ok()
+                .filter(|address| !address.is_empty())
+                .or(default.miner_address),
+            mempool_max_transactions: env_or(
+                "MEMPOOL_MAX_TRANSACTIONS",
+                default.mempool_max_transactions,
+            ),
+            mempool_max_size: env_or("MEMPOOL_MAX_SIZE", default.mempool_max_size),
+            mempool_expiry: env_or("MEMPOOL_EXPIRY", default.mempool_expiry),
+            data_dir: env::var("DATA_DIR").unwrap_or(default.data_dir),
+            http_port: env_or("HTTP_PORT", default.http_port),
+            p2p_port: env_or("P2P_PORT", default.p2p_port),
+            peers: env::var("PEERS")
+                .map(|peers| {
+                    peers
+                        .split(',')
+                        .map(|p| p.trim().to_string())
+                        .filter(|p| !p.is_empty())
+                        .collect()
+                })
+                .unwrap_or(default.peers),
         }
     }
 }
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
@@ -0,0 +1,128 @@
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::transaction::{Transaction, TxOutput};
+use std::collections::{HashMap, HashSet};
+
+/// What the main chain says about an address after some block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountState {
+    pub balance: u64,
+    /// Number of transfers the address has sent.
+    pub nonce: u64,
+    /// Number of transactions paying to or spending from the address.
+    pub tx_count: u64,
+}
+
+/// Account state of every address at every height of the main chain, so balances are
+/// looked up without replaying the transactions.
+#[derive(Debug, Default)]
+pub struct BalanceIndex {
+    /// States of each address with the height of the block that produced them, oldest first.
+    history: HashMap<Vec<u8>, Vec<(usize, AccountState)>>,
+    /// Addresses changed by the block at each height, to roll it back.
+    touched: Vec<Vec<Vec<u8>>>,
+}
+
+impl BalanceIndex {
+    pub fn new() -> Self {
+        BalanceIndex::default()
+    }
+
+    /// Number of blocks applied.
+    pub fn len(&self) -> usize {
+        self.touched.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.touched.is_empty()
+    }
+
+    /// Applies the next block of the main chain, `spent` holds the outputs each of its
+    /// transactions spent as returned by `UtxoSet::apply_block`.
+    pub fn apply_block(&mut self, block: &Block, spent: &[Vec<TxOutput>]) {
+        let height = self.touched.len();
+        let mut changes: HashMap<Vec<u8>, (u64, u64, u64, u64)> = HashMap::new();
+        for (t, spent) in block.transactions.iter().zip(spent) {
+            let Some(tx) = Transaction::try_deserialization(t) else {
+                continue;
+            };
+
+            let mut involved = HashSet::new();
+            // coinbases spend nothing and do not count as sent
+            if !spent.is_empty() {
+                changes.entry(tx.sender_address.clone()).or_default().2 += 1;
+                involved.insert(tx.sender_address.clone());
+            }
+            for output in spent {
+                let change = changes.entry(output.address.clone()).or_default();
+                change.1 = change.1.saturating_add(output.value);
+            }
+            for output in &tx.outputs {
+                let change = changes.entry(output.address.clone()).or_default();
+                change.0 = change.0.saturating_add(output.value);
+                involved.insert(output.address.clone());
+            }
+            for address in involved {
+                changes.entry(address).or_default().3 += 1;
+            }
+        }
+
+        let mut touched = Vec::new();
+        for (address, (received, sent, nonce, tx_count)) in changes {
+            let state = self.get(&address);
+            let state = AccountState {
+                balance: state.balance.saturating_add(received).saturating_sub(sent),
+                nonce: state.nonce + nonce,
+                tx_count: state.tx_count + tx_count,
+            };
+            self.history
+                .entry(address.clone())
+                .or_default()
+                .push((height, state));
+            touched.push(address);
+        }
+        self.touched.push(touched);
+    }
+
+    /// Forgets the blocks from height `len` on.
+    pub fn rollback(&mut self, len: usize) {
+        while self.touched.len() > len {
+            for address in self.touched.pop().unwrap_or_default() {
+                if let Some(states) = self.history.get_mut(&address) {
+                    states.pop();
+                    if states.is_empty() {
+                        self.history.remove(&address);
+                    }
+                }
+            }
+        }
+    }
+
+    /// State of `address` at the tip.
+    pub fn get(&self, address: &[u8]) -> AccountState {
+        self.history
+            .get(address)
+            .and_then(|states| states.last())
+            .map(|(_, state)| *state)
+            .unwrap_or_default()
+    }
+
+    /// Heights of the blocks that changed `address`, oldest first.
+    pub fn heights(&self, address: &[u8]) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        self.history
+            .get(address)
+            .into_iter()
+            .flatten()
+            .map(|(height, _)| *height)
+    }
+
+    /// State of `address` right after the block at `height`.
+    pub fn get_at(&self, address: &[u8], height: usize) -> AccountState {
+        let Some(states) = self.history.get(address) else {
+            return AccountState::default();
+        };
+        match states.partition_point(|(h, _)| *h <= height) {
+            0 => AccountState::default(),
+            i => states[i - 1].1,
+        }
+    }
+}
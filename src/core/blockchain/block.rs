@@ -1,18 +1,31 @@
+use crate::core::blockchain::merkle::{self, MerkleProof};
+use crate::core::blockchain::Serialization;
 use sha2::{Digest, Sha256};
 use std::ops::AddAssign;
 use std::time::SystemTime;
 
-#[derive(Debug)]
-pub struct Block {
+/// The part of a block covered by its hash. Transactions are committed to through
+/// `merkle_root`, so the proof of work hashes a few dozen bytes whatever the block size.
+#[derive(Debug, Clone)]
+pub struct BlockHeader {
     pub nonce: i32,
     pub previous_hash: Vec<u8>,
     pub time_stamp: u128,
+    /// Compact form of the target the hash must not exceed, see `difficulty`.
+    pub target: u32,
+    /// Root of the Merkle tree over the transactions of the block.
+    pub merkle_root: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub header: BlockHeader,
     pub transactions: Vec<Vec<u8>>,
 }
 
 impl AddAssign<i32> for Block {
     fn add_assign(&mut self, rhs: i32) {
-        self.nonce += rhs;
+        self.header.nonce += rhs;
     }
 }
 
@@ -22,35 +35,182 @@ impl PartialEq for Block {
     }
 }
 
+impl BlockHeader {
+    pub fn hash(&self) -> Vec<u8> {
+        let mut bin = Vec::new();
+        bin.extend(self.nonce.to_be_bytes());
+        bin.extend(&self.previous_hash);
+        bin.extend(self.time_stamp.to_be_bytes());
+        bin.extend(self.target.to_be_bytes());
+        bin.extend(&self.merkle_root);
+        let mut hasher = Sha256::new();
+        hasher.update(bin);
+        hasher.finalize().to_vec()
+    }
+
+    /// Same as `deserialization` but returns `None` instead of panicking on malformed bytes.
+    pub fn try_deserialization(bytes: &[u8]) -> Option<BlockHeader> {
+        let mut pos: usize = 0;
+        let mut take = |len: usize| {
+            let field = bytes.get(pos..pos.checked_add(len)?)?;
+            pos += len;
+            Some(field)
+        };
+
+        // Deserialize nonce
+        let nonce = i32::from_be_bytes(take(4)?.try_into().ok()?);
+
+        // Deserialize previous hash with its length
+        let len_hash = usize::from_be_bytes(take(8)?.try_into().ok()?);
+        let previous_hash = take(len_hash)?.to_vec();
+
+        // Deserialize time stamp and target
+        let time_stamp = u128::from_be_bytes(take(16)?.try_into().ok()?);
+        let target = u32::from_be_bytes(take(4)?.try_into().ok()?);
+
+        // Deserialize merkle root with its length
+        let len_root = usize::from_be_bytes(take(8)?.try_into().ok()?);
+        let merkle_root = take(len_root)?.to_vec();
+
+        if pos != bytes.len() {
+            return None;
+        }
+
+        Some(BlockHeader {
+            nonce,
+            previous_hash,
+            time_stamp,
+            target,
+            merkle_root,
+        })
+    }
+}
+
+impl Serialization<BlockHeader> for BlockHeader {
+    fn serialization(&self) -> Vec<u8> {
+        let mut bin = Vec::<u8>::new();
+        bin.extend(self.nonce.to_be_bytes());
+        bin.extend(self.previous_hash.len().to_be_bytes());
+        bin.extend(&self.previous_hash);
+        bin.extend(self.time_stamp.to_be_bytes());
+        bin.extend(self.target.to_be_bytes());
+        bin.extend(self.merkle_root.len().to_be_bytes());
+        bin.extend(&self.merkle_root);
+        bin
+    }
+
+    fn deserialization(bytes: Vec<u8>) -> BlockHeader {
+        BlockHeader::try_deserialization(&bytes).expect("malformed block header bytes")
+    }
+}
+
 impl Block {
-    pub fn new(nonce: i32, previous_hash: Vec<u8>) -> Self {
+    pub fn new(nonce: i32, previous_hash: Vec<u8>, target: u32) -> Self {
         let time_now = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap();
         Block {
-            nonce,
-            previous_hash,
-            time_stamp: time_now.as_nanos(),
+            header: BlockHeader {
+                nonce,
+                previous_hash,
+                time_stamp: time_now.as_nanos(),
+                target,
+                merkle_root: merkle::root(&[]),
+            },
+            transactions: Vec::new(),
+        }
+    }
+
+    /// The first block of every chain, identical on all nodes sharing `target`.
+    pub fn genesis(target: u32) -> Self {
+        Block {
+            header: BlockHeader {
+                nonce: 0,
+                previous_hash: vec![0_u8; 32],
+                time_stamp: 0,
+                target,
+                merkle_root: merkle::root(&[]),
+            },
             transactions: Vec::new(),
         }
     }
 
     pub fn hash(&self) -> Vec<u8> {
-        let mut bin = Vec::new();
-        bin.extend(self.nonce.to_be_bytes());
-        bin.extend(&self.previous_hash);
-        bin.extend(self.time_stamp.to_be_bytes());
+        self.header.hash()
+    }
+
+    /// Recomputes the Merkle root, needed after changing `transactions`.
+    pub fn update_merkle_root(&mut self) {
+        self.header.merkle_root = merkle::root(&self.transactions);
+    }
+
+    /// Whether the header commits to exactly the transactions of the block.
+    pub fn has_valid_merkle_root(&self) -> bool {
+        self.header.merkle_root == merkle::root(&self.transactions)
+    }
+
+    /// Proof that the transaction at `index` is part of this block, checked against
+    /// the header with `merkle::verify`.
+    pub fn merkle_proof(&self, index: usize) -> Option<MerkleProof> {
+        merkle::prove(&self.transactions, index)
+    }
+
+    pub fn print(&self) {
+        println!("timestamp: {:x}", self.header.time_stamp);
+        println!("nonce: {}", self.header.nonce);
+        println!("target: {:08x}", self.header.target);
+        println!("previous_hash: {:?}", self.header.previous_hash);
+        println!("merkle_root: {:?}", self.header.merkle_root);
+    }
+
+    /// Same as `deserialization` but returns `None` instead of panicking on malformed bytes,
+    /// for blocks received from other nodes.
+    pub fn try_deserialization(bytes: &[u8]) -> Option<Block> {
+        let mut pos: usize = 0;
+        let mut take = |len: usize| {
+            let field = bytes.get(pos..pos.checked_add(len)?)?;
+            pos += len;
+            Some(field)
+        };
+
+        // Deserialize header with its length
+        let len_header = usize::from_be_bytes(take(8)?.try_into().ok()?);
+        let header = BlockHeader::try_deserialization(take(len_header)?)?;
+
+        // Deserialize every transaction with its length
+        let tx_count = usize::from_be_bytes(take(8)?.try_into().ok()?);
+        let mut transactions = Vec::new();
+        for _ in 0..tx_count {
+            let len_tx = usize::from_be_bytes(take(8)?.try_into().ok()?);
+            transactions.push(take(len_tx)?.to_vec());
+        }
+
+        if pos != bytes.len() {
+            return None;
+        }
+
+        Some(Block {
+            header,
+            transactions,
+        })
+    }
+}
+
+impl Serialization<Block> for Block {
+    fn serialization(&self) -> Vec<u8> {
+        let header = self.header.serialization();
+        let mut bin = Vec::<u8>::new();
+        bin.extend(header.len().to_be_bytes());
+        bin.extend(header);
+        bin.extend(self.transactions.len().to_be_bytes());
         for tx in &self.transactions {
-            bin.extend(tx.clone());
+            bin.extend(tx.len().to_be_bytes());
+            bin.extend(tx);
         }
-        let mut hasher = Sha256::new();
-        hasher.update(bin);
-        hasher.finalize().to_vec()
+        bin
     }
 
-    pub fn print(&self) {
-        println!("timestamp: {:x}", self.time_stamp);
-        println!("nonce: {}", self.nonce);
-        println!("previous_hash: {:?}", self.previous_hash);
+    fn deserialization(bytes: Vec<u8>) -> Block {
+        Block::try_deserialization(&bytes).expect("malformed block bytes")
     }
 }
@@ -0,0 +1,158 @@
+use crate::config::Config;
+use crate::core::blockchain::block::Block;
+
+/// Targets are 256 bit big endian numbers, a block is valid when its hash is not above
+/// the target. Blocks record them in the compact form `0xEEMMMMMM`: the target is the
+/// 3 byte mantissa `M` shifted so that the number is `E` bytes long.
+pub type Target = [u8; 32];
+
+pub fn expand(bits: u32) -> Target {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = (bits & 0x00ff_ffff).to_be_bytes();
+    let mut target = [0_u8; 32];
+    for (i, byte) in mantissa[1..].iter().enumerate() {
+        // position of the byte counted from the end of the number
+        let from_end = exponent as isize - 1 - i as isize;
+        if (0..32).contains(&from_end) {
+            target[31 - from_end as usize] = *byte;
+        }
+    }
+
+    target
+}
+
+pub fn compact(target: &Target) -> u32 {
+    let Some(first) = target.iter().position(|b| *b != 0) else {
+        return 0;
+    };
+
+    let mut mantissa = [0_u8; 4];
+    for (i, byte) in target[first..].iter().take(3).enumerate() {
+        mantissa[1 + i] = *byte;
+    }
+    ((32 - first) as u32) << 24 | u32::from_be_bytes(mantissa)
+}
+
+/// Target requiring the hex form of the hash to start with `difficulty` zeros.
+pub fn from_difficulty(difficulty: usize) -> u32 {
+    let mut target = [0xff_u8; 32];
+    for nibble in 0..difficulty.min(64) {
+        target[nibble / 2] &= if nibble % 2 == 0 { 0x0f } else { 0x00 };
+    }
+
+    compact(&target)
+}
+
+pub fn meets_target(hash: &[u8], bits: u32) -> bool {
+    hash.len() == 32 && hash <= &expand(bits)[..]
+}
+
+/// Expected number of hashes needed to find a block at `bits`, about 2^256 / target.
+pub fn work(bits: u32) -> u128 {
+    let target = expand(bits);
+    let high = u128::from_be_bytes(target[..16].try_into().unwrap());
+    u128::MAX.checked_div(high).unwrap_or(u128::MAX)
+}
+
+/// Number of blocks whose time stamps give the median time of a block.
+pub const MEDIAN_TIME_BLOCKS: usize = 11;
+
+/// Height and time stamp of the block holding the median time stamp among the one at
+/// `height` and up to `MEDIAN_TIME_BLOCKS - 1` before it, the genesis block left out past
+/// height 0.
+/// Blocks skewing their time stamp cannot move it unless they are the majority.
+pub fn median_time<'a, F>(height: usize, block_at: F) -> (usize, u128)
+where
+    F: Fn(usize) -> &'a Block,
+{
+    let first = (height + 1)
+        .saturating_sub(MEDIAN_TIME_BLOCKS)
+        .max(1)
+        .min(height);
+    let mut times: Vec<(u128, usize)> = (first..=height)
+        .map(|h| (block_at(h).header.time_stamp, h))
+        .collect();
+    times.sort_unstable();
+    let (time, height) = times[times.len() / 2];
+    (height, time)
+}
+
+/// Target of the block at `height`, given access to its ancestors through `block_at`.
+///
+/// Every `Config::retarget_interval` blocks the target is scaled by the time the last
+/// interval actually took over the time it should have taken, limited to a factor of
+/// `Config::max_adjustment` either way and never easier than one leading zero nibble.
+/// The interval is measured between the median times of its first and last block, so
+/// a miner pushing the time stamp of a block ahead does not stretch it. The genesis
+/// block has no meaningful time stamp and is left out of the first interval.
+pub fn next_target<'a, F>(height: usize, block_at: F, config: &Config) -> u32
+where
+    F: Fn(usize) -> &'a Block,
+{
+    let previous = block_at(height - 1);
+    let interval = config.retarget_interval.max(1);
+    if !height.is_multiple_of(interval) {
+        return previous.header.target;
+    }
+
+    let (first, first_time) = median_time(height.saturating_sub(interval).max(1), &block_at);
+    let (last, last_time) = median_time(height - 1, &block_at);
+    let spans = last.saturating_sub(first) as u128;
+    if spans == 0 {
+        return previous.header.target;
+    }
+
+    let expected = spans * config.target_block_time.max(1) as u128 * 1_000_000_000;
+    let max_adjustment = config.max_adjustment.max(1) as u128;
+    let actual = last_time
+        .saturating_sub(first_time)
+        .clamp(expected / max_adjustment, expected * max_adjustment);
+
+    // time spans in nanoseconds fit in a u64 for centuries
+    let target = mul_div(
+        &expand(previous.header.target),
+        u64::try_from(actual).unwrap_or(u64::MAX),
+        u64::try_from(expected).unwrap_or(u64::MAX),
+    );
+    let limit = expand(from_difficulty(1));
+    if target > limit {
+        return compact(&limit);
+    }
+    compact(&target)
+}
+
+/// `target * numerator / denominator`, saturating at the largest target.
+fn mul_div(target: &Target, numerator: u64, denominator: u64) -> Target {
+    // 32 bit limbs, least significant first, so every step fits in a u128
+    let mut limbs: Vec<u128> = target
+        .chunks(4)
+        .rev()
+        .map(|c| u32::from_be_bytes(c.try_into().unwrap()) as u128)
+        .collect();
+
+    let mut carry: u128 = 0;
+    for limb in limbs.iter_mut() {
+        let current = *limb * numerator as u128 + carry;
+        *limb = current & 0xffff_ffff;
+        carry = current >> 32;
+    }
+    limbs.push(carry & 0xffff_ffff);
+    limbs.push(carry >> 32);
+
+    let mut remainder: u128 = 0;
+    for limb in limbs.iter_mut().rev() {
+        let current = remainder << 32 | *limb;
+        *limb = current / denominator.max(1) as u128;
+        remainder = current % denominator.max(1) as u128;
+    }
+
+    if limbs[8..].iter().any(|l| *l != 0) {
+        return [0xff_u8; 32];
+    }
+
+    let mut result = [0_u8; 32];
+    for (i, limb) in limbs[..8].iter().rev().enumerate() {
+        result[i * 4..i * 4 + 4].copy_from_slice(&(*limb as u32).to_be_bytes());
+    }
+    result
+}
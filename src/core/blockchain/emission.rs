@@ -0,0 +1,40 @@
+use crate::config::Config;
+
+/// New coins the coinbase of the block at `height` may create: `Config::reward` halved
+/// every `Config::halving_interval` blocks, and never more than what is left of
+/// `Config::max_supply`. Genesis creates nothing.
+pub fn subsidy(height: usize, config: &Config) -> u64 {
+    if height == 0 {
+        return 0;
+    }
+    scheduled(height, config).min(config.max_supply.saturating_sub(supply(height - 1, config)))
+}
+
+/// Coins created by the blocks up to `height` included.
+pub fn supply(height: usize, config: &Config) -> u64 {
+    let interval = config.halving_interval.max(1);
+    let mut total: u64 = 0;
+    let mut era_start = 0;
+    // the reward reaches zero after 64 halvings at the latest
+    for era in 0..64 {
+        if era_start > height {
+            break;
+        }
+        let era_end = era_start.saturating_add(interval - 1).min(height);
+        // genesis opens the first era but creates nothing
+        let blocks = (era_end + 1).saturating_sub(era_start.max(1));
+        let created = (config.reward >> era).saturating_mul(blocks as u64);
+        total = total.saturating_add(created);
+        era_start = era_start.saturating_add(interval);
+    }
+
+    total.min(config.max_supply)
+}
+
+fn scheduled(height: usize, config: &Config) -> u64 {
+    let halvings = height / config.halving_interval.max(1);
+    if halvings >= 64 {
+        return 0;
+    }
+    config.reward >> halvings
+}
@@ -0,0 +1,284 @@
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::difficulty;
+use crate::core::blockchain::mempool::{Mempool, MempoolEntry};
+use crate::core::blockchain::transaction::Transaction;
+use crate::core::blockchain::utxo::{UtxoSet, UtxoView};
+use crate::core::blockchain::validation::{self, InvalidReason, ValidationError};
+use crate::core::blockchain::{fund_transaction, unix_time};
+use crate::core::blockchain::{AddBlockError, BlockChain, ChainEvent};
+use crate::core::storage::ChainStore;
+use std::io;
+
+/// Deepest fork below the tip the chain follows. Side blocks forking off deeper are
+/// refused and pruned, so peers cannot fill memory with cheap blocks deep in history.
+pub const MAX_FORK_DEPTH: usize = 100;
+/// Most side blocks kept at once.
+pub const MAX_SIDE_BLOCKS: usize = 1_000;
+
+impl BlockChain {
+    /// Adds a block received from another node.
+    ///
+    /// A block on top of the tip is validated and appended. A block on any other known
+    /// block is kept on a side branch, and once that branch carries more work than the
+    /// main chain since their common block the chain reorganizes onto it. Branches forking
+    /// off more than `MAX_FORK_DEPTH` blocks below the tip are refused.
+    pub fn add_block(&mut self, block: Block) -> Result<(), AddBlockError> {
+        let hash = block.hash();
+        if self.side_blocks.contains_key(&hash) || self.main_height(&hash).is_some() {
+            return Err(AddBlockError::AlreadyKnown);
+        }
+
+        if block.header.previous_hash == self.last_block().hash() {
+            self.connect_block(block.clone())?;
+            self.publish(ChainEvent::NewBlock(block));
+            return Ok(());
+        }
+
+        let mut branch = vec![block];
+        let fork_height = loop {
+            let parent = &branch[branch.len() - 1].header.previous_hash;
+            if let Some(height) = self.main_height(parent) {
+                break height;
+            }
+            match self.side_blocks.get(parent) {
+                Some((_, b)) => branch.push(b.clone()),
+                None => return Err(AddBlockError::UnknownParent),
+            }
+        };
+        branch.reverse();
+        if fork_height + MAX_FORK_DEPTH < self.chain.len() - 1 {
+            return Err(AddBlockError::ForkTooDeep);
+        }
+
+        // checking the target and the proof of work first keeps cheap junk off the side
+        // branches, and the merkle root keeps a valid header from carrying other
+        // transactions there
+        let index = fork_height + branch.len();
+        let tip = &branch[branch.len() - 1];
+        let target = difficulty::next_target(
+            index,
+            |height| {
+                if height <= fork_height {
+                    &self.chain[height]
+                } else {
+                    &branch[height - fork_height - 1]
+                }
+            },
+            &self.config,
+        );
+        let reason = if tip.header.target != target {
+            Some(InvalidReason::UnexpectedTarget(target))
+        } else if !difficulty::meets_target(&hash, tip.header.target) {
+            Some(InvalidReason::InsufficientWork)
+        } else if !tip.has_valid_merkle_root() {
+            Some(InvalidReason::MerkleRootMismatch)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(AddBlockError::Invalid(ValidationError { index, reason }));
+        }
+
+        if self.side_blocks.len() >= MAX_SIDE_BLOCKS {
+            return Err(AddBlockError::TooManySideBlocks);
+        }
+        let block = branch[branch.len() - 1].clone();
+        self.side_blocks
+            .insert(hash.clone(), (index, block.clone()));
+
+        let branch_work = self.work_of(&branch);
+        let main_work = self.work_of(&self.chain[fork_height + 1..]);
+        if branch_work <= main_work {
+            println!(
+                "kept block {} at height {} on a side branch",
+                hex::encode(&hash),
+                index
+            );
+            return Ok(());
+        }
+
+        self.reorganize(fork_height, branch)?;
+        self.publish(ChainEvent::NewBlock(block));
+        Ok(())
+    }
+
+    /// Total work of the main chain.
+    pub fn total_work(&self) -> u128 {
+        self.work_of(&self.chain)
+    }
+
+    fn work_of(&self, blocks: &[Block]) -> u128 {
+        blocks
+            .iter()
+            .map(|b| difficulty::work(b.header.target))
+            .fold(0, u128::saturating_add)
+    }
+
+    fn main_height(&self, hash: &[u8]) -> Option<usize> {
+        self.block_heights.get(hash).copied()
+    }
+
+    /// Validates a block on top of the tip and appends it.
+    fn connect_block(&mut self, block: Block) -> Result<(), AddBlockError> {
+        validation::validate_block(
+            self.chain.len(),
+            self.last_block(),
+            &block,
+            self.next_target(),
+            &self.config,
+            &self.utxos,
+        )
+        .map_err(AddBlockError::Invalid)?;
+
+        self.store
+            .append_block(&block)
+            .map_err(AddBlockError::Storage)?;
+        self.chain.push(block);
+        self.index_block(self.chain.len() - 1);
+        self.prune_side_blocks();
+        let pending = self.mempool.take();
+        self.refill_pool(pending);
+        Ok(())
+    }
+
+    /// Forgets the side blocks that fell more than `MAX_FORK_DEPTH` below the tip.
+    fn prune_side_blocks(&mut self) {
+        let tip = self.chain.len() - 1;
+        self.side_blocks
+            .retain(|_, (height, _)| *height + MAX_FORK_DEPTH > tip);
+    }
+
+    /// Switches the main chain to `branch`, which follows the block at `fork_height`.
+    /// The replaced blocks are kept as a side branch and their transactions go back to
+    /// the pool when they are still valid.
+    fn reorganize(&mut self, fork_height: usize, branch: Vec<Block>) -> Result<(), AddBlockError> {
+        let mut utxos = UtxoSet::from_blocks(&self.chain[..=fork_height]);
+        let mut spent = Vec::new();
+        let block_at = |height: usize| {
+            if height <= fork_height {
+                &self.chain[height]
+            } else {
+                &branch[height - fork_height - 1]
+            }
+        };
+        for (i, block) in branch.iter().enumerate() {
+            let height = fork_height + 1 + i;
+            if let Err(e) = validation::validate_block(
+                height,
+                block_at(height - 1),
+                block,
+                difficulty::next_target(height, block_at, &self.config),
+                &self.config,
+                &utxos,
+            ) {
+                // nothing can be built on an invalid block, forget it and its descendants
+                for bad in &branch[i..] {
+                    self.side_blocks.remove(&bad.hash());
+                }
+                return Err(AddBlockError::Invalid(e));
+            }
+            spent.push(utxos.apply_block(block));
+        }
+
+        if let Err(e) = replace_blocks(self.store.as_mut(), fork_height + 1, &branch) {
+            // put the replaced blocks back so the store keeps matching the chain in memory
+            let old = &self.chain[fork_height + 1..];
+            if let Err(e) = replace_blocks(self.store.as_mut(), fork_height + 1, old) {
+                println!(
+                    "failed to restore the stored chain after a reorganization: {}",
+                    e
+                );
+            }
+            return Err(AddBlockError::Storage(e));
+        }
+
+        let old_tip = self.last_block().hash();
+        let disconnected = self.chain.split_off(fork_height + 1);
+        let connected = branch.len();
+        self.utxos = utxos;
+        self.balances.rollback(fork_height + 1);
+        self.unindex_lookups(fork_height + 1, &disconnected);
+        for (block, spent) in branch.into_iter().zip(spent) {
+            self.side_blocks.remove(&block.hash());
+            self.balances.apply_block(&block, &spent);
+            self.chain.push(block);
+            self.index_lookups(self.chain.len() - 1);
+        }
+        let new_tip = self.last_block().hash();
+
+        let now = unix_time();
+        let mut candidates: Vec<MempoolEntry> = disconnected
+            .iter()
+            .flat_map(|b| b.transactions.iter())
+            .filter_map(|t| Transaction::try_deserialization(t))
+            .map(|tx| MempoolEntry::new(tx, now))
+            .collect();
+        candidates.append(&mut self.mempool.take());
+        for (i, block) in disconnected.iter().enumerate() {
+            self.side_blocks
+                .insert(block.hash(), (fork_height + 1 + i, block.clone()));
+        }
+        self.prune_side_blocks();
+        self.refill_pool(candidates);
+
+        println!(
+            "reorganized at height {}: tip {} -> {}, {} blocks disconnected, {} connected",
+            fork_height,
+            hex::encode(&old_tip),
+            hex::encode(&new_tip),
+            disconnected.len(),
+            connected
+        );
+        self.publish(ChainEvent::Reorg {
+            fork_height,
+            old_tip,
+            new_tip,
+            disconnected: disconnected.len(),
+            connected,
+        });
+        Ok(())
+    }
+
+    /// Replaces the pool with `candidates`, dropping coinbases, transfers whose nonce was
+    /// used by the main chain in the meantime and those their sender can no longer pay
+    /// for. A transfer whose inputs are gone is funded again, under the same txid.
+    pub(super) fn refill_pool(&mut self, candidates: Vec<MempoolEntry>) {
+        let mut view = UtxoView::new(&self.utxos);
+        let mut pool = Mempool::new(&self.config);
+        for entry in candidates {
+            let tx = &entry.transaction;
+            if tx.is_coinbase() || tx.nonce != view.next_nonce(&tx.sender_address) {
+                continue;
+            }
+
+            let entry = if validation::check_transfer(0, tx, &view, &self.config).is_ok() {
+                entry
+            } else {
+                match tx
+                    .to_wallet_transaction()
+                    .and_then(|wallet_tx| fund_transaction(&view, &wallet_tx))
+                {
+                    Some(tx) => MempoolEntry::new(tx, entry.received),
+                    None => continue,
+                }
+            };
+            let tx = entry.transaction.clone();
+            match pool.insert(entry) {
+                Ok(()) => view.apply(&tx),
+                Err(e) => println!("dropped a pending transaction: {}", e),
+            }
+        }
+
+        self.mempool = pool;
+        self.save_pool();
+    }
+}
+
+/// Replaces the stored blocks from height `len` on with `blocks`.
+fn replace_blocks(store: &mut dyn ChainStore, len: usize, blocks: &[Block]) -> io::Result<()> {
+    store.truncate(len)?;
+    for block in blocks {
+        store.append_block(block)?;
+    }
+    Ok(())
+}
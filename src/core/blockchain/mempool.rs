@@ -0,0 +1,263 @@
+use crate::config::Config;
+use crate::core::blockchain::transaction::{OutPoint, Transaction};
+use crate::core::blockchain::Serialization;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A pending transaction with what the pool needs to order and expire it.
+#[derive(Debug, Clone)]
+pub struct MempoolEntry {
+    pub transaction: Transaction,
+    pub txid: Vec<u8>,
+    /// The serialized transaction, as it goes into a block.
+    pub bytes: Vec<u8>,
+    /// Unix time in seconds the node accepted the transaction.
+    pub received: u64,
+}
+
+impl MempoolEntry {
+    pub fn new(transaction: Transaction, received: u64) -> Self {
+        let bytes = transaction.serialization();
+        MempoolEntry {
+            txid: transaction.txid(),
+            transaction,
+            bytes,
+            received,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Fee per byte, rounded, for display only.
+    pub fn fee_rate(&self) -> f64 {
+        self.transaction.fee as f64 / self.size().max(1) as f64
+    }
+
+    /// Compares the fees per byte of two entries without the rounding of a division.
+    pub fn cmp_fee_rate(&self, other: &MempoolEntry) -> Ordering {
+        let rate = self.transaction.fee as u128 * other.size() as u128;
+        let other_rate = other.transaction.fee as u128 * self.size() as u128;
+        rate.cmp(&other_rate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    /// The transaction is already pending.
+    Duplicate,
+    /// The pending transaction with this txid spends the same output or uses the same
+    /// nonce of the sender.
+    Conflict(Vec<u8>),
+    /// The pool is full of transactions paying at least as much per byte.
+    Full,
+}
+
+impl fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MempoolError::Duplicate => write!(f, "transaction is already pending"),
+            MempoolError::Conflict(txid) => write!(
+                f,
+                "transaction conflicts with pending transaction {}",
+                hex::encode(txid)
+            ),
+            MempoolError::Full => write!(f, "transaction pool is full"),
+        }
+    }
+}
+
+/// Transactions waiting for a block. The pool only checks them against each other, the
+/// chain checks them against the unspent outputs before they get in.
+#[derive(Debug)]
+pub struct Mempool {
+    /// In the order they were accepted, so each comes after the ones it spends from.
+    entries: Vec<MempoolEntry>,
+    txids: HashSet<Vec<u8>>,
+    /// Txid of the pending transaction spending each output.
+    spends: HashMap<OutPoint, Vec<u8>>,
+    /// Txid of the pending transaction using each sender and nonce.
+    nonces: HashMap<(Vec<u8>, u64), Vec<u8>>,
+    size: usize,
+    max_transactions: usize,
+    max_size: usize,
+    expiry: u64,
+}
+
+impl Mempool {
+    pub fn new(config: &Config) -> Self {
+        Mempool {
+            entries: Vec::new(),
+            txids: HashSet::new(),
+            spends: HashMap::new(),
+            nonces: HashMap::new(),
+            size: 0,
+            max_transactions: config.mempool_max_transactions,
+            max_size: config.mempool_max_size,
+            expiry: config.mempool_expiry,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Serialized size of every pending transaction in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Pending transactions in the order they were accepted.
+    pub fn iter(&self) -> std::slice::Iter<'_, MempoolEntry> {
+        self.entries.iter()
+    }
+
+    pub fn get(&self, txid: &[u8]) -> Option<&MempoolEntry> {
+        if !self.txids.contains(txid) {
+            return None;
+        }
+        self.entries.iter().find(|e| e.txid == txid)
+    }
+
+    /// The pending transaction of `sender` carrying `nonce`.
+    pub fn by_sender(&self, sender: &[u8], nonce: u64) -> Option<&MempoolEntry> {
+        let txid = self.nonces.get(&(sender.to_vec(), nonce))?;
+        self.get(txid)
+    }
+
+    /// Adds `entry` after the pending transactions, failing when it is already there,
+    /// conflicts with one of them or there is no room left.
+    pub fn insert(&mut self, entry: MempoolEntry) -> Result<(), MempoolError> {
+        self.check_conflicts(&entry)?;
+        if !self.has_room(entry.size(), 0, 0) {
+            return Err(MempoolError::Full);
+        }
+
+        self.index(&entry);
+        self.size += entry.size();
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Makes room for `entry` by removing the transactions paying the least per byte,
+    /// as long as they pay less than it and it does not depend on them. Returns what was
+    /// removed, the transactions depending on it are left for the caller to drop.
+    pub fn evict_for(&mut self, entry: &MempoolEntry) -> Result<Vec<MempoolEntry>, MempoolError> {
+        let mut by_rate: Vec<usize> = (0..self.entries.len()).collect();
+        // the newest goes first among equal rates
+        by_rate.sort_by(|a, b| {
+            self.entries[*a]
+                .cmp_fee_rate(&self.entries[*b])
+                .then(b.cmp(a))
+        });
+
+        let ancestors = self.ancestors(entry);
+        let mut victims = HashSet::new();
+        let mut freed = 0;
+        for i in by_rate {
+            if self.has_room(entry.size(), victims.len(), freed) {
+                break;
+            }
+            if ancestors.contains(&self.entries[i].txid) {
+                continue;
+            }
+            if self.entries[i].cmp_fee_rate(entry) != Ordering::Less {
+                return Err(MempoolError::Full);
+            }
+            victims.insert(self.entries[i].txid.clone());
+            freed += self.entries[i].size();
+        }
+        if !self.has_room(entry.size(), victims.len(), freed) {
+            return Err(MempoolError::Full);
+        }
+
+        Ok(self.remove_where(|e| victims.contains(&e.txid)))
+    }
+
+    /// Removes the transactions accepted more than the configured expiry before `now`.
+    pub fn expire(&mut self, now: u64) -> Vec<MempoolEntry> {
+        let expiry = self.expiry;
+        self.remove_where(|e| now.saturating_sub(e.received) >= expiry)
+    }
+
+    /// Removes every pending transaction.
+    pub fn take(&mut self) -> Vec<MempoolEntry> {
+        self.remove_where(|_| true)
+    }
+
+    /// Txids of the pending transactions `entry` needs before it, the earlier ones of
+    /// its sender and those it spends from, and theirs in turn.
+    fn ancestors(&self, entry: &MempoolEntry) -> HashSet<Vec<u8>> {
+        let mut senders = HashSet::from([entry.transaction.sender_address.clone()]);
+        let mut parents: HashSet<&Vec<u8>> =
+            entry.transaction.inputs.iter().map(|i| &i.txid).collect();
+        let mut ancestors = HashSet::new();
+        // every transaction comes after the ones it needs
+        for e in self.entries.iter().rev() {
+            if senders.contains(&e.transaction.sender_address) || parents.contains(&e.txid) {
+                ancestors.insert(e.txid.clone());
+                senders.insert(e.transaction.sender_address.clone());
+                parents.extend(e.transaction.inputs.iter().map(|i| &i.txid));
+            }
+        }
+        ancestors
+    }
+
+    fn check_conflicts(&self, entry: &MempoolEntry) -> Result<(), MempoolError> {
+        if self.txids.contains(&entry.txid) {
+            return Err(MempoolError::Duplicate);
+        }
+        let tx = &entry.transaction;
+        let key = (tx.sender_address.clone(), tx.nonce);
+        let spender = tx
+            .inputs
+            .iter()
+            .find_map(|input| self.spends.get(input))
+            .or_else(|| self.nonces.get(&key));
+        match spender {
+            Some(txid) => Err(MempoolError::Conflict(txid.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a transaction of `size` bytes fits once `count` transactions holding
+    /// `freed` bytes are gone.
+    fn has_room(&self, size: usize, count: usize, freed: usize) -> bool {
+        self.entries.len() - count < self.max_transactions
+            && self.size - freed + size <= self.max_size
+    }
+
+    fn index(&mut self, entry: &MempoolEntry) {
+        let tx = &entry.transaction;
+        self.txids.insert(entry.txid.clone());
+        for input in &tx.inputs {
+            self.spends.insert(input.clone(), entry.txid.clone());
+        }
+        self.nonces
+            .insert((tx.sender_address.clone(), tx.nonce), entry.txid.clone());
+    }
+
+    fn remove_where(&mut self, mut remove: impl FnMut(&MempoolEntry) -> bool) -> Vec<MempoolEntry> {
+        let (removed, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|e| remove(e));
+        self.entries = kept;
+
+        self.txids.clear();
+        self.spends.clear();
+        self.nonces.clear();
+        self.size = 0;
+        for entry in std::mem::take(&mut self.entries) {
+            self.index(&entry);
+            self.size += entry.size();
+            self.entries.push(entry);
+        }
+        removed
+    }
+}
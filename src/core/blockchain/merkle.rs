@@ -0,0 +1,112 @@
+use sha2::{Digest, Sha256};
+
+/// Leaves and inner nodes are hashed with different prefixes, so an inner node can never
+/// be passed off as a transaction.
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Path from a transaction to the Merkle root of its block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleProof {
+    /// Position of the transaction in the block.
+    pub index: usize,
+    /// Number of transactions in the block.
+    pub leaf_count: usize,
+    /// Hashes of the siblings met on the way up, from the leaf level to the root.
+    pub siblings: Vec<Vec<u8>>,
+}
+
+pub fn leaf_hash(transaction: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(transaction);
+    hasher.finalize().to_vec()
+}
+
+fn node_hash(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().to_vec()
+}
+
+/// Hashes one level of the tree into the next. A node without a sibling is carried up
+/// unchanged rather than paired with itself, so two different lists of transactions
+/// never share a root.
+fn next_level(level: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => single.clone(),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Merkle root of `transactions`, all zeros for a block without any.
+pub fn root(transactions: &[Vec<u8>]) -> Vec<u8> {
+    if transactions.is_empty() {
+        return vec![0_u8; 32];
+    }
+
+    let mut level: Vec<Vec<u8>> = transactions.iter().map(|tx| leaf_hash(tx)).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+/// Proof that the transaction at `index` is part of `transactions`.
+pub fn prove(transactions: &[Vec<u8>], index: usize) -> Option<MerkleProof> {
+    if index >= transactions.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut level: Vec<Vec<u8>> = transactions.iter().map(|tx| leaf_hash(tx)).collect();
+    let mut position = index;
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(position ^ 1) {
+            siblings.push(sibling.clone());
+        }
+        level = next_level(&level);
+        position /= 2;
+    }
+
+    Some(MerkleProof {
+        index,
+        leaf_count: transactions.len(),
+        siblings,
+    })
+}
+
+/// Checks that `proof` leads from `transaction` to `root`.
+pub fn verify(transaction: &[u8], proof: &MerkleProof, root: &[u8]) -> bool {
+    if proof.index >= proof.leaf_count {
+        return false;
+    }
+
+    let mut hash = leaf_hash(transaction);
+    let mut siblings = proof.siblings.iter();
+    let mut position = proof.index;
+    let mut width = proof.leaf_count;
+    while width > 1 {
+        if position % 2 == 1 {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            hash = node_hash(sibling, &hash);
+        } else if position + 1 < width {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            hash = node_hash(&hash, sibling);
+        }
+        position /= 2;
+        width = width.div_ceil(2);
+    }
+
+    siblings.next().is_none() && hash == root
+}
@@ -2,12 +2,32 @@ use crate::config::Config;
 use transaction::*;
 pub mod transaction;
 
+use crate::core::miner;
+use crate::core::storage::{ChainStore, FileStore};
 use crate::core::wallet::{Wallet, WalletTransaction};
-use std::ops::Index;
-use std::time::Instant;
+use std::fmt;
+use std::io;
+use std::ops::{Bound, Index, RangeBounds};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::time::{Instant, SystemTime};
+use tokio::sync::broadcast;
+use validation::ValidationError;
+pub mod balance;
 pub mod block;
-
+pub mod difficulty;
+pub mod emission;
+pub mod fork;
+pub mod mempool;
+pub mod merkle;
+pub mod utxo;
+pub mod validation;
+
+use balance::{AccountState, BalanceIndex};
 use block::Block;
+use mempool::{Mempool, MempoolEntry, MempoolError};
+use merkle::MerkleProof;
+use std::collections::HashMap;
+use utxo::{UtxoSet, UtxoView};
 pub trait Serialization<T> {
     fn serialization(&self) -> Vec<u8>;
     fn deserialization(bytes: Vec<u8>) -> T;
@@ -23,7 +43,8 @@ pub enum BlockSearch {
 }
 
 pub enum BlockSearchResult<'a> {
-    Success(&'a Block),
+    /// The block found and its height.
+    Success(usize, &'a Block),
     FailOfEmptyBlocks,
     FailOfIndex(usize),
     FailOfPreviousHash(Vec<u8>),
@@ -33,12 +54,83 @@ pub enum BlockSearchResult<'a> {
     FailOfTransaction(Vec<u8>),
 }
 
+#[derive(Debug)]
+pub enum AddBlockError {
+    Invalid(ValidationError),
+    Storage(io::Error),
+    /// The block is already in the main chain or on a side branch.
+    AlreadyKnown,
+    /// The parent of the block is not known, its ancestors must be fetched first.
+    UnknownParent,
+    /// The block forks off more than `fork::MAX_FORK_DEPTH` blocks below the tip.
+    ForkTooDeep,
+    /// `fork::MAX_SIDE_BLOCKS` side blocks are already kept.
+    TooManySideBlocks,
+}
+
+impl fmt::Display for AddBlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddBlockError::Invalid(e) => write!(f, "{}", e),
+            AddBlockError::Storage(e) => write!(f, "failed to persist the block: {}", e),
+            AddBlockError::AlreadyKnown => write!(f, "block is already known"),
+            AddBlockError::UnknownParent => write!(f, "parent block is unknown"),
+            AddBlockError::ForkTooDeep => write!(
+                f,
+                "block forks off more than {} blocks below the tip",
+                fork::MAX_FORK_DEPTH
+            ),
+            AddBlockError::TooManySideBlocks => write!(f, "too many side blocks are kept"),
+        }
+    }
+}
+
+/// Changes to the chain, published to every subscriber.
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    NewTransaction(WalletTransaction),
+    NewBlock(Block),
+    /// The main chain switched to a branch with more work.
+    Reorg {
+        fork_height: usize,
+        old_tip: Vec<u8>,
+        new_tip: Vec<u8>,
+        disconnected: usize,
+        connected: usize,
+    },
+}
+
+/// A transaction found by its txid.
+#[derive(Debug, Clone)]
+pub struct TransactionRecord {
+    pub transaction: Transaction,
+    /// Height of the block holding it and its position there, `None` while pending.
+    pub location: Option<(usize, usize)>,
+    /// Number of blocks from its block up to the tip, zero while pending.
+    pub confirmations: usize,
+}
+
 #[derive(Debug)]
 pub struct BlockChain {
     pub config: Config,
-    transaction_pool: Vec<Vec<u8>>,
+    mempool: Mempool,
     chain: Vec<Block>,
-    blockchain_address: String,
+    /// Unspent outputs of the main chain, kept in step with `chain`.
+    utxos: UtxoSet,
+    /// Account state of every address at every height of `chain`.
+    balances: BalanceIndex,
+    /// Height of the block and position in it of every main chain transaction, by txid.
+    tx_index: HashMap<Vec<u8>, (usize, usize)>,
+    /// Height of every main chain block, by hash.
+    block_heights: HashMap<Vec<u8>, usize>,
+    /// Heights of the main chain blocks carrying each nonce, lowest first.
+    nonce_index: HashMap<i32, Vec<usize>>,
+    /// Valid looking blocks that are not on the main chain with their heights, by hash.
+    side_blocks: HashMap<Vec<u8>, (usize, Block)>,
+    /// Address the coinbase of the blocks this node mines pays to.
+    reward_address: String,
+    store: Box<dyn ChainStore>,
+    events: broadcast::Sender<ChainEvent>,
 }
 
 impl Index<usize> for BlockChain {
@@ -51,35 +143,253 @@ impl Index<usize> for BlockChain {
 }
 
 impl BlockChain {
-    pub fn new(config: Config, address: String) -> Self {
+    /// Opens the chain persisted in `config.data_dir`, creating it on first start.
+    pub fn new(config: Config, address: String) -> io::Result<Self> {
+        let store = FileStore::open(&config.data_dir)?;
+        BlockChain::with_store(config, address, Box::new(store))
+    }
+
+    /// Replays the blocks and pending transactions kept by `store`. Fails with
+    /// `InvalidData` when the stored chain does not pass validation under `config`.
+    pub fn with_store(
+        config: Config,
+        address: String,
+        mut store: Box<dyn ChainStore>,
+    ) -> io::Result<Self> {
+        let chain = store.load_blocks()?;
+        let pool = store.load_pool()?;
         let mut bc = BlockChain {
+            mempool: Mempool::new(&config),
             config,
-            transaction_pool: Vec::<Vec<u8>>::new(),
-            chain: Vec::<Block>::new(),
-            blockchain_address: address,
+            chain,
+            utxos: UtxoSet::new(),
+            balances: BalanceIndex::new(),
+            tx_index: HashMap::new(),
+            block_heights: HashMap::new(),
+            nonce_index: HashMap::new(),
+            side_blocks: HashMap::new(),
+            reward_address: address,
+            store,
+            events: broadcast::channel(64).0,
         };
 
-        let b = Block::new(0, vec![0 as u8; 32]);
-        bc.chain.push(b);
-        bc.mining();
+        // the rules depend on the settings, a chain failing them may only have been mined
+        // under other ones, so it is left on disk untouched for the operator to look at
+        if let Err(e) = bc.validate_chain() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "stored chain does not pass validation, {}; check the settings it was \
+                     mined with",
+                    e
+                ),
+            ));
+        }
+        for height in 0..bc.chain.len() {
+            bc.index_block(height);
+        }
+        // the age of pending transactions is not kept, they wait a full expiry again
+        let now = unix_time();
+        let pool = pool
+            .iter()
+            .filter_map(|t| Transaction::try_deserialization(t))
+            .map(|tx| MempoolEntry::new(tx, now))
+            .collect();
+        bc.refill_pool(pool);
+
+        if bc.chain.is_empty() {
+            let b = Block::genesis(difficulty::from_difficulty(bc.config.difficulty));
+            bc.store.append_block(&b)?;
+            bc.chain.push(b);
+            bc.index_block(0);
+            if !bc.mining() {
+                return Err(io::Error::other("failed to mine the first block"));
+            }
+        }
 
-        bc
+        Ok(bc)
     }
 
-    pub fn create_block(&mut self, nonce: i32, previous_hash: Vec<u8>) {
-        let mut b = Block::new(nonce, previous_hash);
-        for tx in self.transaction_pool.iter() {
-            b.transactions.push(tx.clone());
+    /// Block on top of the tip holding a coinbase paying the subsidy and fees to the reward
+    /// address, followed by the best paying pending transactions that fit the limits of
+    /// `Config`. Its proof of work is left to the caller, see `miner::solve`.
+    pub fn block_template(&mut self) -> Block {
+        self.expire_transactions();
+        let mut b = Block::new(0, self.last_block().hash(), self.next_target());
+        // the tip may be stamped a little ahead of our clock
+        let previous_time = self.last_block().header.time_stamp;
+        b.header.time_stamp = b.header.time_stamp.max(previous_time + 1);
+        let (selected, fees) = self.select_transactions();
+        let subsidy = emission::subsidy(self.chain.len(), &self.config);
+        let coinbase = self.coinbase_transaction(subsidy.saturating_add(fees));
+        b.transactions.push(coinbase.serialization());
+        let entries: Vec<&MempoolEntry> = self.mempool.iter().collect();
+        for i in selected {
+            b.transactions.push(entries[i].bytes.clone());
+        }
+        b.update_merkle_root();
+        b
+    }
+
+    /// Picks pending transactions by highest fee per byte until the block is full. A
+    /// transaction is only taken once the transactions it depends on are, so the result
+    /// keeps the order they must be applied in. Returns their pool positions and fees.
+    fn select_transactions(&self) -> (Vec<usize>, u64) {
+        let mut base = Block::new(0, self.last_block().hash(), 0);
+        base.transactions
+            .push(self.coinbase_transaction(0).serialization());
+        let mut space = self
+            .config
+            .max_block_size
+            .saturating_sub(base.serialization().len());
+        let mut slots = self.config.max_block_transactions.saturating_sub(1);
+
+        let mut candidates: Vec<Option<&MempoolEntry>> = self.mempool.iter().map(Some).collect();
+        let mut view = UtxoView::new(&self.utxos);
+        let mut selected = Vec::new();
+        let mut fees: u64 = 0;
+        while slots > 0 {
+            // reversed so that the first arrived wins a tie
+            let best = candidates
+                .iter()
+                .enumerate()
+                .rev()
+                .filter_map(|(i, c)| Some((i, (*c)?)))
+                .filter(|(_, entry)| {
+                    let tx = &entry.transaction;
+                    block_size(entry) <= space
+                        && tx.nonce == view.next_nonce(&tx.sender_address)
+                        && tx.inputs.iter().all(|input| view.get(input).is_some())
+                })
+                .max_by(|(_, a), (_, b)| a.cmp_fee_rate(b))
+                .map(|(i, _)| i);
+            let Some(entry) = best.and_then(|i| candidates[i].take()) else {
+                break;
+            };
+            selected.extend(best);
+
+            view.apply(&entry.transaction);
+            fees = fees.saturating_add(entry.transaction.fee);
+            space -= block_size(entry);
+            slots -= 1;
+        }
+
+        (selected, fees)
+    }
+
+    /// Coinbase of the next block paying `value` to the reward address.
+    fn coinbase_transaction(&self, value: u64) -> Transaction {
+        Transaction::coinbase(
+            self.chain.len() as u64,
+            self.reward_address.as_bytes().to_vec(),
+            value,
+        )
+    }
+
+    /// Hashes of the main chain from the tip back to genesis, dense for the last ten
+    /// blocks and then doubling the step, so a peer can find the last common block.
+    pub fn locator(&self) -> Vec<Vec<u8>> {
+        let mut locator = Vec::new();
+        let mut height = self.chain.len() - 1;
+        let mut step = 1;
+        loop {
+            locator.push(self.chain[height].hash());
+            if height == 0 {
+                break;
+            }
+            if locator.len() >= 10 {
+                step *= 2;
+            }
+            height = height.saturating_sub(step);
+        }
+
+        locator
+    }
+
+    /// Finds the highest block of `locator` in the main chain and returns its height with
+    /// up to `limit` blocks that follow it.
+    pub fn blocks_after(&self, locator: &[Vec<u8>], limit: usize) -> (usize, Vec<Block>) {
+        let fork_height = locator
+            .iter()
+            .find_map(|hash| self.block_heights.get(hash).copied())
+            .unwrap_or(0);
+        let blocks = self
+            .chain
+            .iter()
+            .skip(fork_height + 1)
+            .take(limit)
+            .cloned()
+            .collect();
+
+        (fork_height, blocks)
+    }
+
+    /// Receives every transaction and block accepted from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<ChainEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: ChainEvent) {
+        // no subscriber is not an error
+        let _ = self.events.send(event);
+    }
+
+    pub fn len(&self) -> usize {
+        self.chain.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    /// Checks the links, proof of work and transactions of every block.
+    pub fn validate_chain(&self) -> Result<(), ValidationError> {
+        validation::validate_chain(&self.chain, &self.config).map(|_| ())
+    }
+
+    /// Applies the main chain block at `height` to the unspent outputs and the balances,
+    /// every block before it must already be applied.
+    fn index_block(&mut self, height: usize) {
+        let spent = self.utxos.apply_block(&self.chain[height]);
+        self.balances.apply_block(&self.chain[height], &spent);
+        self.index_lookups(height);
+    }
+
+    /// Adds the main chain block at `height` to the lookups of `search_block`.
+    fn index_lookups(&mut self, height: usize) {
+        let block = &self.chain[height];
+        for (position, t) in block.transactions.iter().enumerate() {
+            self.tx_index.insert(txid_of(t), (height, position));
+        }
+        self.block_heights.insert(block.hash(), height);
+        self.nonce_index
+            .entry(block.header.nonce)
+            .or_default()
+            .push(height);
+    }
+
+    /// Removes the blocks from height `len` on, no longer in the main chain, from the
+    /// lookups of `search_block`.
+    fn unindex_lookups(&mut self, len: usize, blocks: &[Block]) {
+        for block in blocks {
+            for t in &block.transactions {
+                self.tx_index.remove(&txid_of(t));
+            }
+            self.block_heights.remove(&block.hash());
+            if let Some(heights) = self.nonce_index.get_mut(&block.header.nonce) {
+                heights.retain(|h| *h < len);
+                if heights.is_empty() {
+                    self.nonce_index.remove(&block.header.nonce);
+                }
+            }
+        }
+    }
+
+    fn save_pool(&mut self) {
+        let pool: Vec<Vec<u8>> = self.mempool.iter().map(|e| e.bytes.clone()).collect();
+        if let Err(e) = self.store.save_pool(&pool) {
+            println!("failed to persist the transaction pool: {}", e);
         }
-        self.transaction_pool.clear();
-        let now = Instant::now();
-        let proof_hash = BlockChain::do_proof_of_work(&mut b, self.config.difficulty);
-        let elapsed = now.elapsed();
-        println!(
-            "compute time: {:?}\nproof for the current block is :{:?}",
-            elapsed, proof_hash
-        );
-        self.chain.push(b);
     }
 
     pub fn print(&self) {
@@ -98,154 +408,347 @@ impl BlockChain {
         &self.chain[0]
     }
 
-    pub fn search_block(&self, search: BlockSearch) -> BlockSearchResult {
-        for (idx, block) in self.chain.iter().enumerate() {
-            match search {
-                BlockSearch::SearchByIndex(index) => {
-                    if idx == index {
-                        return BlockSearchResult::Success(block);
-                    }
+    /// Finds a main chain block through the indexes kept in step with the chain. Nonces
+    /// may repeat, the lowest block carrying one is returned.
+    pub fn search_block(&self, search: BlockSearch) -> BlockSearchResult<'_> {
+        if self.chain.is_empty() {
+            return BlockSearchResult::FailOfEmptyBlocks;
+        }
 
-                    if index >= self.chain.len() {
-                        return BlockSearchResult::FailOfIndex(index);
-                    }
-                }
-                BlockSearch::SearchByPreviousHash(ref hash) => {
-                    if block.previous_hash == *hash {
-                        return BlockSearchResult::Success(block);
-                    }
-
-                    if idx >= self.chain.len() {
-                        return BlockSearchResult::FailOfPreviousHash(hash.to_vec());
-                    }
-                }
+        let height = match search {
+            BlockSearch::SearchByIndex(index) => Some(index)
+                .filter(|h| *h < self.chain.len())
+                .ok_or(BlockSearchResult::FailOfIndex(index)),
+            BlockSearch::SearchByPreviousHash(hash) => self
+                .block_heights
+                .get(&hash)
+                .map(|h| h + 1)
+                .filter(|h| *h < self.chain.len())
+                .ok_or(BlockSearchResult::FailOfPreviousHash(hash)),
+            BlockSearch::SearchByBlockHash(hash) => self
+                .block_heights
+                .get(&hash)
+                .copied()
+                .ok_or(BlockSearchResult::FailOfBlockHash(hash)),
+            BlockSearch::SearchByNonce(nonce) => self
+                .nonce_index
+                .get(&nonce)
+                .and_then(|heights| heights.first().copied())
+                .ok_or(BlockSearchResult::FailOfNonce(nonce)),
+            BlockSearch::SearchByTimeStamp(time_stamp) => {
+                // time stamps strictly increase along the main chain
+                let h = self
+                    .chain
+                    .partition_point(|b| b.header.time_stamp < time_stamp);
+                Some(h)
+                    .filter(|h| {
+                        self.chain
+                            .get(*h)
+                            .is_some_and(|b| b.header.time_stamp == time_stamp)
+                    })
+                    .ok_or(BlockSearchResult::FailOfTimeStamp(time_stamp))
+            }
+            BlockSearch::SearchByTransaction(transaction) => self
+                .tx_index
+                .get(&txid_of(&transaction))
+                .map(|(h, _)| *h)
+                .ok_or(BlockSearchResult::FailOfTransaction(transaction)),
+        };
 
-                BlockSearch::SearchByBlockHash(ref hash) => {
-                    if block.hash() == *hash {
-                        return BlockSearchResult::Success(block);
-                    }
+        match height {
+            Ok(height) => BlockSearchResult::Success(height, &self.chain[height]),
+            Err(failure) => failure,
+        }
+    }
 
-                    if idx >= self.chain.len() {
-                        return BlockSearchResult::FailOfBlockHash(hash.to_vec());
-                    }
-                }
+    /// Main chain blocks with a height in `heights`, and the height of the first of them.
+    pub fn blocks_by_height(&self, heights: impl RangeBounds<usize>) -> (usize, &[Block]) {
+        let start = match heights.start_bound() {
+            Bound::Included(h) => *h,
+            Bound::Excluded(h) => h.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+        .min(self.chain.len());
+        let end = match heights.end_bound() {
+            Bound::Included(h) => h.saturating_add(1),
+            Bound::Excluded(h) => *h,
+            Bound::Unbounded => self.chain.len(),
+        }
+        .clamp(start, self.chain.len());
+        (start, &self.chain[start..end])
+    }
 
-                BlockSearch::SearchByNonce(nonce) => {
-                    if block.nonce == nonce {
-                        return BlockSearchResult::Success(block);
-                    }
+    /// Main chain blocks with a time stamp in `window`, in unix nanoseconds, and the
+    /// height of the first of them.
+    pub fn blocks_by_time(&self, window: impl RangeBounds<u128>) -> (usize, &[Block]) {
+        let start = self.chain.partition_point(|b| match window.start_bound() {
+            Bound::Included(t) => b.header.time_stamp < *t,
+            Bound::Excluded(t) => b.header.time_stamp <= *t,
+            Bound::Unbounded => false,
+        });
+        let end = self.chain.partition_point(|b| match window.end_bound() {
+            Bound::Included(t) => b.header.time_stamp <= *t,
+            Bound::Excluded(t) => b.header.time_stamp < *t,
+            Bound::Unbounded => true,
+        });
+        (start, &self.chain[start..end.max(start)])
+    }
 
-                    if idx >= self.chain.len() {
-                        return BlockSearchResult::FailOfNonce(nonce);
-                    }
-                }
+    /// Height of the main chain block holding the transaction `txid` and the proof that
+    /// it does, which can be checked against that block header with `merkle::verify`.
+    pub fn prove_transaction(&self, txid: &[u8]) -> Option<(usize, MerkleProof)> {
+        let (height, position) = *self.tx_index.get(txid)?;
+        Some((height, self.chain[height].merkle_proof(position)?))
+    }
 
-                BlockSearch::SearchByTimeStamp(time_stamp) => {
-                    if block.time_stamp == time_stamp {
-                        return BlockSearchResult::Success(block);
-                    }
+    /// Finds the transaction `txid` in the main chain or in the pool.
+    pub fn find_transaction(&self, txid: &[u8]) -> Option<TransactionRecord> {
+        if let Some((height, position)) = self.tx_index.get(txid) {
+            let bytes = &self.chain[*height].transactions[*position];
+            return Some(TransactionRecord {
+                transaction: Transaction::try_deserialization(bytes)?,
+                location: Some((*height, *position)),
+                confirmations: self.chain.len() - height,
+            });
+        }
 
-                    if idx >= self.chain.len() {
-                        return BlockSearchResult::FailOfTimeStamp(time_stamp);
-                    }
-                }
+        self.mempool.get(txid).map(|entry| TransactionRecord {
+            transaction: entry.transaction.clone(),
+            location: None,
+            confirmations: 0,
+        })
+    }
 
-                BlockSearch::SearchByTransaction(ref transaction) => {
-                    for tx in block.transactions.iter() {
-                        if tx == transaction {
-                            return BlockSearchResult::Success(block);
-                        }
+    /// Transactions of the main chain block at `height`, in block order.
+    pub fn block_transactions(&self, height: usize) -> Vec<TransactionRecord> {
+        let Some(block) = self.chain.get(height) else {
+            return Vec::new();
+        };
+        block
+            .transactions
+            .iter()
+            .enumerate()
+            .filter_map(|(position, bytes)| {
+                Some(TransactionRecord {
+                    transaction: Transaction::try_deserialization(bytes)?,
+                    location: Some((height, position)),
+                    confirmations: self.chain.len() - height,
+                })
+            })
+            .collect()
+    }
 
-                        if idx >= self.chain.len() {
-                            return BlockSearchResult::FailOfTransaction(transaction.to_vec());
-                        }
-                    }
-                }
-            }
-        }
+    /// Number of transactions in the main chain, coinbases included.
+    pub fn transaction_count(&self) -> usize {
+        self.tx_index.len()
+    }
 
-        BlockSearchResult::FailOfEmptyBlocks
+    /// Transactions sent by or paying to `address`, the pending ones first and then those
+    /// of the main chain from the tip down.
+    pub fn address_history<'a>(
+        &'a self,
+        address: &'a str,
+    ) -> impl Iterator<Item = TransactionRecord> + 'a {
+        let address = address.as_bytes();
+        let involves = move |tx: &Transaction| {
+            (!tx.is_coinbase() && tx.sender_address == address)
+                || tx.outputs.iter().any(|output| output.address == address)
+        };
+        let pending = self
+            .mempool
+            .iter()
+            .rev()
+            .filter(move |entry| involves(&entry.transaction))
+            .map(|entry| TransactionRecord {
+                transaction: entry.transaction.clone(),
+                location: None,
+                confirmations: 0,
+            });
+        // only the blocks that changed the balance can hold its transactions
+        let confirmed = self
+            .balances
+            .heights(address)
+            .rev()
+            .flat_map(|height| self.block_transactions(height).into_iter().rev())
+            .filter(move |record| involves(&record.transaction));
+        pending.chain(confirmed)
+    }
+
+    /// Address the blocks mined by this node pay their coinbase to.
+    pub fn reward_address(&self) -> &str {
+        &self.reward_address
+    }
+
+    /// Pays the coinbase of the next blocks mined by this node to `address`.
+    pub fn set_reward_address(&mut self, address: String) {
+        self.reward_address = address;
+    }
+
+    /// Transactions waiting for a block.
+    pub fn mempool(&self) -> &Mempool {
+        &self.mempool
+    }
+
+    /// Drops the pending transactions that waited longer than `Config::mempool_expiry`
+    /// and those spending from them. Returns how many expired.
+    pub fn expire_transactions(&mut self) -> usize {
+        let expired = self.mempool.expire(unix_time());
+        if !expired.is_empty() {
+            println!("{} pending transactions expired", expired.len());
+            let pending = self.mempool.take();
+            self.refill_pool(pending);
+        }
+        expired.len()
     }
 
     pub fn add_transaction(&mut self, tx: &WalletTransaction) -> bool {
-        if tx.sender == self.blockchain_address {
-            println!("minner cannot send money to himself");
+        if !Wallet::verify_transaction(tx, self.config.network) {
+            println!("invalid transaction");
             return false;
         }
 
-        if tx.sender != self.config.sender && !Wallet::verify_transaction(tx) {
-            println!("invalid transaction");
+        if !Wallet::is_signed_by_sender(tx) {
+            println!("public key does not belong to the sender");
             return false;
         }
 
-        if tx.sender != self.config.sender
-            && self.calculate_total_amount(tx.sender.clone()) < tx.amount as i64
-        {
-            println!("sender dose not have enough balance");
+        self.expire_transactions();
+        // the same transfer gossiped again, or another one using its nonce
+        if let Some(pending) = self.mempool.by_sender(tx.sender.as_bytes(), tx.nonce) {
+            let error = if hex::encode(&pending.transaction.signature) == tx.signature {
+                MempoolError::Duplicate
+            } else {
+                MempoolError::Conflict(pending.txid.clone())
+            };
+            println!("{}", error);
             return false;
         }
 
-        let transaction = Transaction::new(
-            tx.sender.as_bytes().to_vec(),
-            tx.recipient.as_bytes().to_vec(),
-            tx.amount,
-        );
+        // a replayed transaction carries a used nonce
+        let view = self.pool_view();
+        let expected = view.next_nonce(tx.sender.as_bytes());
+        if tx.nonce != expected {
+            println!(
+                "transaction nonce is {} but the sender is at {}",
+                tx.nonce, expected
+            );
+            return false;
+        }
 
-        for tx_in_pool in self.transaction_pool.iter() {
-            if *tx_in_pool == transaction.serialization() {
-                break;
+        let Some(transaction) = fund_transaction(&view, tx) else {
+            println!("sender dose not have enough balance");
+            return false;
+        };
+
+        let entry = MempoolEntry::new(transaction, unix_time());
+        let evicted = match self.mempool.evict_for(&entry) {
+            Ok(evicted) => evicted,
+            Err(e) => {
+                println!("{}", e);
+                return false;
             }
+        };
+        if let Err(e) = self.mempool.insert(entry) {
+            println!("{}", e);
+            return false;
         }
 
-        self.transaction_pool.push(transaction.serialization());
+        if evicted.is_empty() {
+            self.save_pool();
+        } else {
+            println!("evicted {} pending transactions paying less", evicted.len());
+            // the transactions spending from the evicted ones are funded again or dropped
+            let pending = self.mempool.take();
+            self.refill_pool(pending);
+        }
+        self.publish(ChainEvent::NewTransaction(tx.clone()));
         true
     }
 
-    fn do_proof_of_work(block: &mut Block, difficulty: usize) -> String {
-        loop {
-            let hash = block.hash();
-            let hash_str = hex::encode(&hash);
-            if hash_str[0..difficulty] == "0".repeat(difficulty) {
-                return hash_str;
-            }
-
-            *block += 1;
+    /// Unspent outputs once every pending transaction is applied.
+    fn pool_view(&self) -> UtxoView<'_> {
+        let mut view = UtxoView::new(&self.utxos);
+        for entry in self.mempool.iter() {
+            view.apply(&entry.transaction);
         }
+        view
     }
 
+    /// Nonce the next transaction of `address` must carry, counting pending ones.
+    pub fn next_nonce(&self, address: &str) -> u64 {
+        self.pool_view().next_nonce(address.as_bytes())
+    }
+
+    /// Target the next block on top of the tip must meet.
+    pub fn next_target(&self) -> u32 {
+        difficulty::next_target(self.chain.len(), |h| &self.chain[h], &self.config)
+    }
+
+    /// Mines the next block while holding the chain, which is how the first block is
+    /// made. A running node mines through `miner::Miner` instead.
     pub fn mining(&mut self) -> bool {
-        let tx = WalletTransaction {
-            sender: self.config.sender.into(),
-            recipient: self.blockchain_address.clone().into(),
-            amount: self.config.reward,
-            public_key: "".to_string(),
-            signature: "".to_string(),
+        let template = self.block_template();
+        let now = Instant::now();
+        let hashes = AtomicU64::new(0);
+        let Some(block) = miner::solve(
+            &template,
+            self.config.mining_threads,
+            &AtomicBool::new(false),
+            &hashes,
+        ) else {
+            return false;
         };
+        println!(
+            "compute time: {:?}\nproof for the current block is :{:?}",
+            now.elapsed(),
+            hex::encode(block.hash())
+        );
 
-        self.add_transaction(&tx);
-        self.create_block(0, self.last_block().hash());
-
+        if let Err(e) = self.add_block(block) {
+            println!("failed to add the mined block: {}", e);
+            return false;
+        }
         true
     }
 
     pub fn calculate_total_amount(&self, address: String) -> i64 {
-        let mut total_amount: i64 = 0;
-        for i in 0..self.chain.len() {
-            let block = &self[i];
-            for t in block.transactions.iter() {
-                let tx = Transaction::deserialization(t.clone());
-                let value = tx.value;
-
-                if <String as Into<Vec<u8>>>::into(address.clone()) == tx.recipient_address {
-                    total_amount += value as i64;
-                }
+        i64::try_from(self.account(&address).balance).unwrap_or(i64::MAX)
+    }
 
-                if <String as Into<Vec<u8>>>::into(address.clone()) == tx.sender_address {
-                    total_amount -= value as i64;
-                }
-            }
-        }
+    /// Balance, nonce and transaction count of `address` at the tip.
+    pub fn account(&self, address: &str) -> AccountState {
+        self.balances.get(address.as_bytes())
+    }
 
-        total_amount
+    /// State of `address` right after the block at `height`, `None` past the tip.
+    pub fn account_at(&self, address: &str, height: usize) -> Option<AccountState> {
+        if height >= self.chain.len() {
+            return None;
+        }
+        Some(self.balances.get_at(address.as_bytes(), height))
     }
 }
+
+/// Picks outputs of the sender in `view` to pay for `tx` and its fee, `None` if they are
+/// not enough.
+fn fund_transaction(view: &UtxoView, tx: &WalletTransaction) -> Option<Transaction> {
+    let cost = tx.amount.checked_add(tx.fee)?;
+    let (inputs, total) = view.select(tx.sender.as_bytes(), cost)?;
+    Some(Transaction::from_wallet_transaction(
+        tx,
+        inputs,
+        total - cost,
+    ))
+}
+
+/// Bytes a pending transaction takes in a block, its length prefix included.
+fn block_size(entry: &MempoolEntry) -> usize {
+    entry.size() + 8
+}
+
+pub(super) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -1,79 +1,289 @@
 use crate::core::blockchain::*;
+use crate::core::wallet::scheme::Scheme;
+use sha2::{Digest, Sha256};
 use std::fmt;
 
-#[derive(Debug)]
+/// Reference to an output of an earlier transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OutPoint {
+    pub txid: Vec<u8>,
+    pub index: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxOutput {
+    pub address: Vec<u8>,
+    pub value: u64,
+}
+
+/// Whether a transaction moves coins or creates them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    Transfer,
+    /// Pays the reward and fees to the miner of the block at `height`, always its first
+    /// transaction. The height gives the coinbase of every block its own txid.
+    Coinbase {
+        height: u64,
+    },
+}
+
+/// A transfer spending outputs of earlier transactions that all belong to `sender_address`,
+/// or the coinbase of a block which has no sender and no inputs.
+///
+/// The first output of a transfer pays the recipient, an optional second one returns the
+/// change to the sender and what the inputs hold beyond the outputs is the fee of the miner.
+#[derive(Debug, Clone)]
 pub struct Transaction {
+    pub kind: TxKind,
     pub sender_address: Vec<u8>,
-    pub recipient_address: Vec<u8>,
-    pub value: u64,
+    /// Number of transfers the sender made before this one.
+    pub nonce: u64,
+    /// Paid to the miner, the inputs must hold the outputs plus the fee.
+    pub fee: u64,
+    pub inputs: Vec<OutPoint>,
+    pub outputs: Vec<TxOutput>,
+    /// Scheme of the key of the sender, P-256 for a coinbase.
+    pub scheme: Scheme,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
 }
 
 impl Transaction {
-    pub fn new(sender: Vec<u8>, recipient: Vec<u8>, value: u64) -> Transaction {
+    pub fn new(sender: Vec<u8>, inputs: Vec<OutPoint>, outputs: Vec<TxOutput>) -> Transaction {
         Transaction {
+            kind: TxKind::Transfer,
             sender_address: sender,
-            recipient_address: recipient,
-            value,
+            nonce: 0,
+            fee: 0,
+            inputs,
+            outputs,
+            scheme: Scheme::default(),
+            public_key: Vec::new(),
+            signature: Vec::new(),
         }
     }
+
+    /// Coinbase of the block at `height` paying `value` to `address`.
+    pub fn coinbase(height: u64, address: Vec<u8>, value: u64) -> Transaction {
+        Transaction {
+            kind: TxKind::Coinbase { height },
+            ..Transaction::new(Vec::new(), Vec::new(), vec![TxOutput { address, value }])
+        }
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        matches!(self.kind, TxKind::Coinbase { .. })
+    }
+
+    /// Builds the on-chain form of a wallet transaction spending `inputs`, returning
+    /// `change` to the sender. The key and signature are kept so that every node can
+    /// verify it again.
+    pub fn from_wallet_transaction(
+        tx: &WalletTransaction,
+        inputs: Vec<OutPoint>,
+        change: u64,
+    ) -> Transaction {
+        let mut outputs = vec![TxOutput {
+            address: tx.recipient.as_bytes().to_vec(),
+            value: tx.amount,
+        }];
+        if change > 0 {
+            outputs.push(TxOutput {
+                address: tx.sender.as_bytes().to_vec(),
+                value: change,
+            });
+        }
+
+        Transaction {
+            kind: TxKind::Transfer,
+            sender_address: tx.sender.as_bytes().to_vec(),
+            nonce: tx.nonce,
+            fee: tx.fee,
+            inputs,
+            outputs,
+            scheme: tx.scheme,
+            public_key: hex::decode(&tx.public_key).unwrap_or_default(),
+            signature: hex::decode(&tx.signature).unwrap_or_default(),
+        }
+    }
+
+    /// Rebuilds the wallet transaction that was signed, `None` for a coinbase, if the
+    /// addresses are not utf-8 or the outputs are not a payment followed by an optional
+    /// change to the sender.
+    pub fn to_wallet_transaction(&self) -> Option<WalletTransaction> {
+        if self.is_coinbase() {
+            return None;
+        }
+        let (payment, change) = match self.outputs.as_slice() {
+            [payment] => (payment, None),
+            [payment, change] => (payment, Some(change)),
+            _ => return None,
+        };
+        if change.is_some_and(|c| c.address != self.sender_address) {
+            return None;
+        }
+
+        Some(WalletTransaction {
+            sender: String::from_utf8(self.sender_address.clone()).ok()?,
+            recipient: String::from_utf8(payment.address.clone()).ok()?,
+            amount: payment.value,
+            fee: self.fee,
+            nonce: self.nonce,
+            scheme: self.scheme,
+            public_key: hex::encode(&self.public_key),
+            signature: hex::encode(&self.signature),
+        })
+    }
+
+    /// Hash identifying the transaction, the outputs it creates are spent through it.
+    ///
+    /// A transfer is identified by what its sender signed: the inputs and the change the
+    /// node funded it with are left out, so funding it again keeps its txid. The nonce
+    /// still makes it unique, the sender cannot use it twice.
+    pub fn txid(&self) -> Vec<u8> {
+        if self.is_coinbase() {
+            return Sha256::digest(self.serialization()).to_vec();
+        }
+        let signed = Transaction {
+            inputs: Vec::new(),
+            outputs: self.outputs.iter().take(1).cloned().collect(),
+            ..self.clone()
+        };
+        Sha256::digest(signed.serialization()).to_vec()
+    }
+
+    /// Sum of the outputs, `None` on overflow.
+    pub fn output_value(&self) -> Option<u64> {
+        self.outputs
+            .iter()
+            .try_fold(0_u64, |total, o| total.checked_add(o.value))
+    }
+
+    /// Same as `deserialization` but returns `None` instead of panicking on malformed bytes,
+    /// for transactions received from other nodes.
+    pub fn try_deserialization(bytes: &[u8]) -> Option<Transaction> {
+        let mut pos = 0;
+        let mut next_field = || {
+            let len = usize::from_be_bytes(bytes.get(pos..pos + 8)?.try_into().ok()?);
+            pos += 8;
+            let field = bytes.get(pos..pos.checked_add(len)?)?.to_vec();
+            pos += len;
+            Some(field)
+        };
+
+        let kind = match next_field()?.as_slice() {
+            [0] => TxKind::Transfer,
+            [1] => TxKind::Coinbase {
+                height: u64::from_be_bytes(next_field()?.try_into().ok()?),
+            },
+            _ => return None,
+        };
+        let sender_address = next_field()?;
+        let nonce = u64::from_be_bytes(next_field()?.try_into().ok()?);
+        let fee = u64::from_be_bytes(next_field()?.try_into().ok()?);
+
+        let input_count = usize::from_be_bytes(next_field()?.try_into().ok()?);
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            let txid = next_field()?;
+            let index = u32::from_be_bytes(next_field()?.try_into().ok()?);
+            inputs.push(OutPoint { txid, index });
+        }
+
+        let output_count = usize::from_be_bytes(next_field()?.try_into().ok()?);
+        let mut outputs = Vec::new();
+        for _ in 0..output_count {
+            let address = next_field()?;
+            let value = u64::from_be_bytes(next_field()?.try_into().ok()?);
+            outputs.push(TxOutput { address, value });
+        }
+
+        let scheme = match next_field()?.as_slice() {
+            [tag] => Scheme::from_tag(*tag)?,
+            _ => return None,
+        };
+        let public_key = next_field()?;
+        let signature = next_field()?;
+        if pos != bytes.len() {
+            return None;
+        }
+
+        Some(Transaction {
+            kind,
+            sender_address,
+            nonce,
+            fee,
+            inputs,
+            outputs,
+            scheme,
+            public_key,
+            signature,
+        })
+    }
+}
+
+/// Txid of a serialized transaction, the hash of the bytes when they are not one.
+pub fn txid_of(bytes: &[u8]) -> Vec<u8> {
+    match Transaction::try_deserialization(bytes) {
+        Some(tx) => tx.txid(),
+        None => Sha256::digest(bytes).to_vec(),
+    }
 }
 
 impl Serialization<Transaction> for Transaction {
     fn serialization(&self) -> Vec<u8> {
         let mut bin = Vec::<u8>::new();
-        let len_sender = self.sender_address.len();
-        bin.extend(len_sender.to_be_bytes().to_vec());
-        bin.extend(&self.sender_address);
-        let len_recipient = self.recipient_address.len();
-        bin.extend(len_recipient.to_be_bytes().to_vec());
-        bin.extend(&self.recipient_address);
-        let len_value = self.value.to_be_bytes().len();
-        bin.extend(len_value.to_be_bytes().to_vec());
-        bin.extend(self.value.to_be_bytes().to_vec());
+        let mut push_field = |field: &[u8]| {
+            bin.extend(field.len().to_be_bytes());
+            bin.extend(field);
+        };
+
+        match self.kind {
+            TxKind::Transfer => push_field(&[0]),
+            TxKind::Coinbase { height } => {
+                push_field(&[1]);
+                push_field(&height.to_be_bytes());
+            }
+        }
+        push_field(&self.sender_address);
+        push_field(&self.nonce.to_be_bytes());
+        push_field(&self.fee.to_be_bytes());
+        push_field(&self.inputs.len().to_be_bytes());
+        for input in &self.inputs {
+            push_field(&input.txid);
+            push_field(&input.index.to_be_bytes());
+        }
+        push_field(&self.outputs.len().to_be_bytes());
+        for output in &self.outputs {
+            push_field(&output.address);
+            push_field(&output.value.to_be_bytes());
+        }
+        push_field(&[self.scheme.tag()]);
+        push_field(&self.public_key);
+        push_field(&self.signature);
         bin
     }
 
     fn deserialization(bytes: Vec<u8>) -> Transaction {
-        let mut pos = 0;
-
-        // Deserialize sender address length
-        let len_sender = usize::from_be_bytes(bytes[pos..pos + 8].try_into().unwrap());
-        pos += 8;
-        let mut sender_address = Vec::<u8>::new();
-        sender_address.extend_from_slice(&bytes[pos..pos + len_sender]);
-        pos += len_sender;
-
-        // Deserialize recipient address length
-        let len_recipient = usize::from_be_bytes(bytes[pos..pos + 8].try_into().unwrap());
-        pos += 8;
-        let mut recipient_address = Vec::<u8>::new();
-        recipient_address.extend_from_slice(&bytes[pos..pos + len_recipient]);
-        pos += len_recipient;
-
-        // Deserialize value length and value itself
-        let len_value = usize::from_be_bytes(bytes[pos..pos + 8].try_into().unwrap());
-        pos += 8;
-        let value: u64 = u64::from_be_bytes(bytes[pos..pos + len_value].try_into().unwrap());
-
-        // Return the deserialized Transaction
-        Transaction {
-            sender_address,
-            recipient_address,
-            value,
-        }
+        Transaction::try_deserialization(&bytes).expect("malformed transaction bytes")
     }
 }
 
 impl fmt::Display for Transaction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}\nsender address: {:?}\nrecipient address:{:?}\nvalue:{}\n{}",
-            "-".repeat(40),
-            self.sender_address,
-            self.recipient_address,
-            self.value,
-            "-".repeat(40)
-        )
+        writeln!(f, "{}", "-".repeat(40))?;
+        if let TxKind::Coinbase { height } = self.kind {
+            writeln!(f, "coinbase of block {}", height)?;
+        }
+        writeln!(f, "sender address: {:?}", self.sender_address)?;
+        writeln!(f, "nonce: {}", self.nonce)?;
+        writeln!(f, "fee: {}", self.fee)?;
+        for input in &self.inputs {
+            writeln!(f, "input: {}:{}", hex::encode(&input.txid), input.index)?;
+        }
+        for output in &self.outputs {
+            writeln!(f, "output: {:?} value:{}", output.address, output.value)?;
+        }
+        write!(f, "{}", "-".repeat(40))
     }
 }
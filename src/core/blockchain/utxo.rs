@@ -0,0 +1,183 @@
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::transaction::{OutPoint, Transaction, TxOutput};
+use std::collections::{HashMap, HashSet};
+
+/// Outputs of the main chain that are not spent yet, with the nonce the next transfer of
+/// every sender must carry.
+#[derive(Debug, Clone, Default)]
+pub struct UtxoSet {
+    outputs: HashMap<OutPoint, TxOutput>,
+    nonces: HashMap<Vec<u8>, u64>,
+}
+
+impl UtxoSet {
+    pub fn new() -> Self {
+        UtxoSet::default()
+    }
+
+    /// Replays `blocks` from genesis, they must be valid.
+    pub fn from_blocks(blocks: &[Block]) -> Self {
+        let mut utxos = UtxoSet::new();
+        for block in blocks {
+            utxos.apply_block(block);
+        }
+        utxos
+    }
+
+    /// Spends the inputs and adds the outputs of every transaction of a validated block.
+    /// Returns the outputs spent by each transaction.
+    pub fn apply_block(&mut self, block: &Block) -> Vec<Vec<TxOutput>> {
+        let mut spent = Vec::new();
+        for t in &block.transactions {
+            let Some(tx) = Transaction::try_deserialization(t) else {
+                spent.push(Vec::new());
+                continue;
+            };
+            let spent_outputs: Vec<TxOutput> = tx
+                .inputs
+                .iter()
+                .filter_map(|input| self.outputs.remove(input))
+                .collect();
+            if !spent_outputs.is_empty() {
+                self.nonces
+                    .insert(tx.sender_address.clone(), tx.nonce.saturating_add(1));
+            }
+            spent.push(spent_outputs);
+            let txid = tx.txid();
+            for (index, output) in tx.outputs.into_iter().enumerate() {
+                let outpoint = OutPoint {
+                    txid: txid.clone(),
+                    index: index as u32,
+                };
+                self.outputs.insert(outpoint, output);
+            }
+        }
+        spent
+    }
+
+    pub fn get(&self, outpoint: &OutPoint) -> Option<&TxOutput> {
+        self.outputs.get(outpoint)
+    }
+
+    /// Nonce the next transfer of `address` must carry.
+    pub fn next_nonce(&self, address: &[u8]) -> u64 {
+        self.nonces.get(address).copied().unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Sum of the unspent outputs of `address`.
+    pub fn balance(&self, address: &[u8]) -> u64 {
+        self.outputs
+            .values()
+            .filter(|o| o.address == address)
+            .fold(0, |total, o| total.saturating_add(o.value))
+    }
+}
+
+/// The set as seen by pending transactions: outputs they spend are hidden and the outputs
+/// they create can be spent in turn, without touching the set itself.
+#[derive(Debug)]
+pub struct UtxoView<'a> {
+    base: &'a UtxoSet,
+    spent: HashSet<OutPoint>,
+    created: HashMap<OutPoint, TxOutput>,
+    nonces: HashMap<Vec<u8>, u64>,
+}
+
+impl<'a> UtxoView<'a> {
+    pub fn new(base: &'a UtxoSet) -> Self {
+        UtxoView {
+            base,
+            spent: HashSet::new(),
+            created: HashMap::new(),
+            nonces: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, outpoint: &OutPoint) -> Option<&TxOutput> {
+        if self.spent.contains(outpoint) {
+            return None;
+        }
+        self.created
+            .get(outpoint)
+            .or_else(|| self.base.get(outpoint))
+    }
+
+    /// Marks `outpoint` as spent and returns its output, `None` if it is unknown or
+    /// already spent.
+    pub fn spend(&mut self, outpoint: &OutPoint) -> Option<TxOutput> {
+        let output = self.get(outpoint)?.clone();
+        self.spent.insert(outpoint.clone());
+        Some(output)
+    }
+
+    /// Makes the outputs of `tx` spendable.
+    pub fn add_outputs(&mut self, tx: &Transaction) {
+        let txid = tx.txid();
+        for (index, output) in tx.outputs.iter().enumerate() {
+            let outpoint = OutPoint {
+                txid: txid.clone(),
+                index: index as u32,
+            };
+            self.created.insert(outpoint, output.clone());
+        }
+    }
+
+    pub fn next_nonce(&self, address: &[u8]) -> u64 {
+        match self.nonces.get(address) {
+            Some(nonce) => *nonce,
+            None => self.base.next_nonce(address),
+        }
+    }
+
+    /// Spends the inputs and adds the outputs of an accepted transaction. A transaction
+    /// that spends outputs is a transfer and uses up the nonce of its sender.
+    pub fn apply(&mut self, tx: &Transaction) {
+        let mut is_transfer = false;
+        for input in &tx.inputs {
+            is_transfer |= self.spend(input).is_some();
+        }
+        if is_transfer {
+            self.nonces
+                .insert(tx.sender_address.clone(), tx.nonce.saturating_add(1));
+        }
+        self.add_outputs(tx);
+    }
+
+    /// Picks unspent outputs of `address` worth at least `amount`, in outpoint order so the
+    /// choice does not depend on hash map iteration. Returns them with their total value.
+    pub fn select(&self, address: &[u8], amount: u64) -> Option<(Vec<OutPoint>, u64)> {
+        let mut candidates: Vec<(&OutPoint, &TxOutput)> = self
+            .base
+            .outputs
+            .iter()
+            .chain(self.created.iter())
+            .filter(|(outpoint, output)| {
+                output.address == address && !self.spent.contains(*outpoint)
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut inputs = Vec::new();
+        let mut total: u64 = 0;
+        for (outpoint, output) in candidates {
+            if total >= amount && !inputs.is_empty() {
+                break;
+            }
+            inputs.push(outpoint.clone());
+            total = total.checked_add(output.value)?;
+        }
+
+        if total < amount || inputs.is_empty() {
+            return None;
+        }
+        Some((inputs, total))
+    }
+}
@@ -0,0 +1,289 @@
+use crate::config::Config;
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::transaction::{Transaction, TxKind};
+use crate::core::blockchain::utxo::{UtxoSet, UtxoView};
+use crate::core::blockchain::Serialization;
+use crate::core::blockchain::{difficulty, emission, unix_time};
+use crate::core::wallet::Wallet;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Seconds the time stamp of a block may run ahead of the clock of the node.
+pub const MAX_FUTURE_DRIFT: u64 = 2 * 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvalidReason {
+    /// The first block is not the expected empty genesis block.
+    InvalidGenesis,
+    /// `previous_hash` is not the hash of the block before it.
+    BrokenLink,
+    /// The block records another target than the retargeting rules give.
+    UnexpectedTarget(u32),
+    /// The block hash is above its target.
+    InsufficientWork,
+    /// The Merkle root in the header does not match the transactions of the block.
+    MerkleRootMismatch,
+    /// The block is larger or holds more transactions than the configured limits.
+    OversizedBlock,
+    /// `time_stamp` is not later than the one of the block before it.
+    NonMonotonicTimeStamp,
+    /// `time_stamp` is more than `MAX_FUTURE_DRIFT` seconds ahead of the clock of the node.
+    FutureTimeStamp,
+    /// The first transaction of the block is not a coinbase.
+    MissingCoinbase,
+    /// The transaction at this position is a coinbase but not the first one.
+    MisplacedCoinbase(usize),
+    /// The coinbase names this height instead of the one of its block.
+    WrongCoinbaseHeight(u64),
+    /// The transaction at this position cannot be decoded.
+    MalformedTransaction(usize),
+    /// The transaction at this position is not signed by its public key.
+    BadSignature(usize),
+    /// The public key of the transaction at this position is not the one of its sender.
+    KeyMismatch(usize),
+    /// The transaction at this position does not carry the next nonce of its sender,
+    /// it was already included or skips one.
+    BadNonce(usize),
+    /// The transaction at this position spends an output that is unknown or already spent.
+    DoubleSpend(usize),
+    /// The transaction at this position spends an output of another address.
+    ForeignInput(usize),
+    /// The outputs and fee of the transaction at this position do not add up to its inputs.
+    UnbalancedTransaction(usize),
+    /// The coinbase pays more than the subsidy of its height and the fees of the block.
+    OversizedReward(u64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// Height of the offending block.
+    pub index: usize,
+    pub reason: InvalidReason,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block {} is invalid: ", self.index)?;
+        match &self.reason {
+            InvalidReason::InvalidGenesis => write!(f, "unexpected genesis block"),
+            InvalidReason::BrokenLink => write!(f, "previous hash does not match"),
+            InvalidReason::UnexpectedTarget(expected) => {
+                write!(f, "target should be {:08x}", expected)
+            }
+            InvalidReason::InsufficientWork => write!(f, "insufficient proof of work"),
+            InvalidReason::MerkleRootMismatch => write!(f, "merkle root does not match"),
+            InvalidReason::OversizedBlock => write!(f, "block exceeds the size limits"),
+            InvalidReason::NonMonotonicTimeStamp => write!(f, "time stamp is not increasing"),
+            InvalidReason::FutureTimeStamp => write!(f, "time stamp is too far in the future"),
+            InvalidReason::MissingCoinbase => write!(f, "first transaction is not a coinbase"),
+            InvalidReason::MisplacedCoinbase(pos) => {
+                write!(f, "transaction {} is a coinbase but not the first", pos)
+            }
+            InvalidReason::WrongCoinbaseHeight(height) => {
+                write!(f, "coinbase is for height {}", height)
+            }
+            InvalidReason::MalformedTransaction(pos) => {
+                write!(f, "transaction {} is malformed", pos)
+            }
+            InvalidReason::BadSignature(pos) => {
+                write!(f, "transaction {} has a bad signature", pos)
+            }
+            InvalidReason::KeyMismatch(pos) => {
+                write!(f, "transaction {} is not signed by its sender", pos)
+            }
+            InvalidReason::BadNonce(pos) => {
+                write!(f, "transaction {} has an unexpected nonce", pos)
+            }
+            InvalidReason::DoubleSpend(pos) => {
+                write!(f, "transaction {} spends a missing or spent output", pos)
+            }
+            InvalidReason::ForeignInput(pos) => {
+                write!(f, "transaction {} spends an output of another address", pos)
+            }
+            InvalidReason::UnbalancedTransaction(pos) => {
+                write!(
+                    f,
+                    "transaction {} outputs and fee do not match its inputs",
+                    pos
+                )
+            }
+            InvalidReason::OversizedReward(amount) => {
+                write!(f, "reward of {} is too large", amount)
+            }
+        }
+    }
+}
+
+/// Walks the whole chain and returns its unspent outputs, or the first invalid block.
+pub fn validate_chain(chain: &[Block], config: &Config) -> Result<UtxoSet, ValidationError> {
+    let mut utxos = UtxoSet::new();
+    let Some(genesis) = chain.first() else {
+        return Ok(utxos);
+    };
+
+    validate_genesis(genesis, config)?;
+    utxos.apply_block(genesis);
+    for index in 1..chain.len() {
+        let target = difficulty::next_target(index, |h| &chain[h], config);
+        validate_block(
+            index,
+            &chain[index - 1],
+            &chain[index],
+            target,
+            config,
+            &utxos,
+        )?;
+        utxos.apply_block(&chain[index]);
+    }
+
+    Ok(utxos)
+}
+
+pub fn validate_genesis(block: &Block, config: &Config) -> Result<(), ValidationError> {
+    if *block != Block::genesis(difficulty::from_difficulty(config.difficulty)) {
+        return Err(ValidationError {
+            index: 0,
+            reason: InvalidReason::InvalidGenesis,
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks `block` at height `index` on top of `previous`, where the retargeting rules
+/// give `target` and `utxos` are the outputs left unspent by the blocks before it.
+/// The set is left untouched, apply the block to it once it is accepted.
+pub fn validate_block(
+    index: usize,
+    previous: &Block,
+    block: &Block,
+    target: u32,
+    config: &Config,
+    utxos: &UtxoSet,
+) -> Result<(), ValidationError> {
+    let fail = |reason| Err(ValidationError { index, reason });
+
+    if block.header.previous_hash != previous.hash() {
+        return fail(InvalidReason::BrokenLink);
+    }
+
+    if block.header.time_stamp <= previous.header.time_stamp {
+        return fail(InvalidReason::NonMonotonicTimeStamp);
+    }
+
+    let latest = u128::from(unix_time().saturating_add(MAX_FUTURE_DRIFT)) * 1_000_000_000;
+    if block.header.time_stamp > latest {
+        return fail(InvalidReason::FutureTimeStamp);
+    }
+
+    if block.header.target != target {
+        return fail(InvalidReason::UnexpectedTarget(target));
+    }
+
+    if !difficulty::meets_target(&block.hash(), block.header.target) {
+        return fail(InvalidReason::InsufficientWork);
+    }
+
+    if !block.has_valid_merkle_root() {
+        return fail(InvalidReason::MerkleRootMismatch);
+    }
+
+    if block.transactions.len() > config.max_block_transactions
+        || block.serialization().len() > config.max_block_size
+    {
+        return fail(InvalidReason::OversizedBlock);
+    }
+
+    if block.transactions.is_empty() {
+        return fail(InvalidReason::MissingCoinbase);
+    }
+
+    let mut view = UtxoView::new(utxos);
+    let mut reward: u64 = 0;
+    let mut fees: u64 = 0;
+    for (pos, t) in block.transactions.iter().enumerate() {
+        let Some(tx) = Transaction::try_deserialization(t) else {
+            return fail(InvalidReason::MalformedTransaction(pos));
+        };
+
+        match (pos, tx.kind) {
+            (0, TxKind::Coinbase { height }) => {
+                if height != index as u64 {
+                    return fail(InvalidReason::WrongCoinbaseHeight(height));
+                }
+                if !tx.inputs.is_empty() || !tx.sender_address.is_empty() || tx.fee != 0 {
+                    return fail(InvalidReason::MalformedTransaction(pos));
+                }
+                reward = tx.output_value().unwrap_or(u64::MAX);
+            }
+            (0, TxKind::Transfer) => return fail(InvalidReason::MissingCoinbase),
+            (_, TxKind::Coinbase { .. }) => return fail(InvalidReason::MisplacedCoinbase(pos)),
+            (_, TxKind::Transfer) => {
+                if let Err(reason) = check_transfer(pos, &tx, &view, config) {
+                    return fail(reason);
+                }
+                fees = fees.saturating_add(tx.fee);
+            }
+        }
+
+        view.apply(&tx);
+    }
+
+    if reward > emission::subsidy(index, config).saturating_add(fees) {
+        return fail(InvalidReason::OversizedReward(reward));
+    }
+
+    Ok(())
+}
+
+/// Checks the signature for the network of `config`, nonce, inputs and outputs of a
+/// transfer against `view`.
+pub fn check_transfer(
+    pos: usize,
+    tx: &Transaction,
+    view: &UtxoView,
+    config: &Config,
+) -> Result<(), InvalidReason> {
+    let Some(wallet_tx) = tx.to_wallet_transaction() else {
+        return Err(InvalidReason::MalformedTransaction(pos));
+    };
+    if tx.inputs.is_empty() {
+        return Err(InvalidReason::MalformedTransaction(pos));
+    }
+
+    if !Wallet::verify_transaction(&wallet_tx, config.network) {
+        return Err(InvalidReason::BadSignature(pos));
+    }
+
+    if !Wallet::is_signed_by_sender(&wallet_tx) {
+        return Err(InvalidReason::KeyMismatch(pos));
+    }
+
+    if tx.nonce != view.next_nonce(&tx.sender_address) {
+        return Err(InvalidReason::BadNonce(pos));
+    }
+
+    let mut input_value: Option<u64> = Some(0);
+    let mut inputs = HashSet::new();
+    for input in &tx.inputs {
+        if !inputs.insert(input) {
+            return Err(InvalidReason::DoubleSpend(pos));
+        }
+        match view.get(input) {
+            Some(output) if output.address == tx.sender_address => {
+                input_value = input_value.and_then(|v| v.checked_add(output.value));
+            }
+            Some(_) => return Err(InvalidReason::ForeignInput(pos)),
+            None => return Err(InvalidReason::DoubleSpend(pos)),
+        }
+    }
+
+    let spent_value = tx
+        .output_value()
+        .and_then(|value| value.checked_add(tx.fee));
+    if input_value.is_none() || input_value != spent_value {
+        return Err(InvalidReason::UnbalancedTransaction(pos));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use crate::core::blockchain::BlockChain;
+use crate::core::miner::{Miner, MiningError};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tokio::time;
+
+/// How often the mempool is looked at while waiting for enough transactions.
+const MEMPOOL_POLL: Duration = Duration::from_secs(1);
+/// Pause after a failed search, so a broken chain does not spin the loop.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// When a node mines on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiningMode {
+    /// The next block as soon as the previous one is found.
+    Continuous,
+    /// A block every so many seconds.
+    Interval(u64),
+    /// A block once so many transactions are pending.
+    Mempool(usize),
+}
+
+impl FromStr for MiningMode {
+    type Err = String;
+
+    /// Reads `continuous`, `interval[:seconds]` or `mempool[:transactions]`, an interval
+    /// of 10 seconds and a threshold of 1 transaction when left out.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.trim().split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (s.trim(), None),
+        };
+        let number = |default: u64| -> Result<u64, String> {
+            value.map_or(Ok(default), |v| {
+                v.parse::<u64>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or(format!("invalid mining mode parameter: {}", v))
+            })
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "continuous" if value.is_none() => Ok(MiningMode::Continuous),
+            "interval" => Ok(MiningMode::Interval(number(10)?)),
+            "mempool" => Ok(MiningMode::Mempool(number(1)? as usize)),
+            _ => Err(format!("unknown mining mode: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for MiningMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MiningMode::Continuous => write!(f, "continuous"),
+            MiningMode::Interval(seconds) => write!(f, "interval:{}", seconds),
+            MiningMode::Mempool(threshold) => write!(f, "mempool:{}", threshold),
+        }
+    }
+}
+
+/// The running loop of an `AutoMiner`.
+#[derive(Debug)]
+struct Task {
+    mode: MiningMode,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Runs a `Miner` in a loop on a background task until stopped.
+#[derive(Debug)]
+pub struct AutoMiner {
+    miner: Arc<Miner>,
+    chain: Arc<RwLock<BlockChain>>,
+    runtime: Handle,
+    task: Mutex<Option<Task>>,
+    blocks_mined: Arc<AtomicU64>,
+}
+
+impl AutoMiner {
+    /// Must be called from within a tokio runtime, the loop runs on it.
+    pub fn new(miner: Arc<Miner>, chain: Arc<RwLock<BlockChain>>) -> Self {
+        AutoMiner {
+            miner,
+            chain,
+            runtime: Handle::current(),
+            task: Mutex::new(None),
+            blocks_mined: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Starts mining in `mode`, false when it already runs.
+    pub fn start(&self, mode: MiningMode) -> bool {
+        let mut task = self.task.lock().unwrap();
+        if task.as_ref().is_some_and(|task| !task.handle.is_finished()) {
+            return false;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = self.runtime.spawn(run(
+            self.miner.clone(),
+            self.chain.clone(),
+            mode,
+            stop.clone(),
+            self.blocks_mined.clone(),
+        ));
+        *task = Some(Task { mode, stop, handle });
+        println!("auto mining started in {} mode", mode);
+        true
+    }
+
+    /// Stops mining and abandons the block being searched, false when it was not running.
+    /// A search started by `Miner::mine` is left alone.
+    pub fn stop(&self) -> bool {
+        let Some(task) = self.task.lock().unwrap().take() else {
+            return false;
+        };
+        // the search runs on a blocking thread that aborting the task does not reach
+        task.stop.store(true, Ordering::SeqCst);
+        if task.handle.is_finished() {
+            return false;
+        }
+        task.handle.abort();
+        println!("auto mining stopped");
+        true
+    }
+
+    /// Mode of the running loop, `None` when stopped.
+    pub fn mode(&self) -> Option<MiningMode> {
+        match &*self.task.lock().unwrap() {
+            Some(task) if !task.handle.is_finished() => Some(task.mode),
+            _ => None,
+        }
+    }
+
+    /// Blocks mined by the loop since the node started.
+    pub fn blocks_mined(&self) -> u64 {
+        self.blocks_mined.load(Ordering::Relaxed)
+    }
+
+    pub fn miner(&self) -> &Miner {
+        &self.miner
+    }
+}
+
+async fn run(
+    miner: Arc<Miner>,
+    chain: Arc<RwLock<BlockChain>>,
+    mode: MiningMode,
+    stop: Arc<AtomicBool>,
+    blocks_mined: Arc<AtomicU64>,
+) {
+    while !stop.load(Ordering::SeqCst) {
+        match mode {
+            MiningMode::Continuous => {}
+            MiningMode::Interval(seconds) => time::sleep(Duration::from_secs(seconds)).await,
+            MiningMode::Mempool(threshold) => loop {
+                let pending = chain.read().unwrap().mempool().len();
+                if pending >= threshold {
+                    break;
+                }
+                time::sleep(MEMPOOL_POLL).await;
+            },
+        }
+
+        let (m, s) = (miner.clone(), stop.clone());
+        match tokio::task::spawn_blocking(move || m.mine_until(&s)).await {
+            Ok(Ok(_)) => {
+                blocks_mined.fetch_add(1, Ordering::Relaxed);
+            }
+            // another block took the tip, the next one is mined on top of it
+            Ok(Err(MiningError::Cancelled)) => {}
+            Ok(Err(e)) => {
+                println!("auto mining: {}", e);
+                time::sleep(RETRY_DELAY).await;
+            }
+            Err(e) => {
+                println!("auto mining stopped: {}", e);
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::{difficulty, AddBlockError, BlockChain, ChainEvent};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::TryRecvError;
+
+pub mod auto;
+
+/// Hashes a worker computes between two looks at the cancel flag.
+const CHECK_EVERY: u64 = 1024;
+/// How often a running search looks for a new tip.
+const TIP_POLL: Duration = Duration::from_millis(50);
+
+/// Work done by one search for a proof of work.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MiningStats {
+    pub hashes: u64,
+    pub elapsed: Duration,
+}
+
+impl MiningStats {
+    /// Hashes per second.
+    pub fn hash_rate(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.hashes as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A block this node mined and appended to its chain.
+#[derive(Debug, Clone)]
+pub struct MinedBlock {
+    pub height: usize,
+    pub block: Block,
+    pub stats: MiningStats,
+}
+
+#[derive(Debug)]
+pub enum MiningError {
+    /// Another search is running.
+    Busy,
+    /// The search was stopped, or the tip changed before the block was found.
+    Cancelled,
+    /// The chain did not accept the mined block.
+    Rejected(AddBlockError),
+}
+
+impl fmt::Display for MiningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MiningError::Busy => write!(f, "a block is already being mined"),
+            MiningError::Cancelled => write!(f, "mining was cancelled"),
+            MiningError::Rejected(e) => write!(f, "mined block was rejected: {}", e),
+        }
+    }
+}
+
+/// Mines blocks for a shared chain. The chain is only locked to build the block and to
+/// append it, the proof of work runs on `threads` threads in between and is abandoned
+/// as soon as another block extends the tip.
+#[derive(Debug)]
+pub struct Miner {
+    chain: Arc<RwLock<BlockChain>>,
+    threads: usize,
+    /// Cancel flag of the running search, `None` when idle. Every search gets its own, so
+    /// a cancel can neither be lost to the next search nor reach it.
+    search: Mutex<Option<Arc<AtomicBool>>>,
+    /// Hashes computed by the running search.
+    hashes: AtomicU64,
+    /// When the running search started, `None` when idle.
+    started: Mutex<Option<Instant>>,
+    last: Mutex<MiningStats>,
+}
+
+impl Miner {
+    pub fn new(chain: Arc<RwLock<BlockChain>>, threads: usize) -> Self {
+        Miner {
+            chain,
+            threads: threads.max(1),
+            search: Mutex::new(None),
+            hashes: AtomicU64::new(0),
+            started: Mutex::new(None),
+            last: Mutex::new(MiningStats::default()),
+        }
+    }
+
+    /// Mines the next block and appends it to the chain, blocking the calling thread
+    /// until it is found or the search is cancelled.
+    pub fn mine(&self) -> Result<MinedBlock, MiningError> {
+        self.mine_until(&AtomicBool::new(false))
+    }
+
+    /// Same as `mine`, also giving up once `stop` is set. A block found after that is
+    /// not appended.
+    pub fn mine_until(&self, stop: &AtomicBool) -> Result<MinedBlock, MiningError> {
+        let cancel = {
+            let mut search = self.search.lock().unwrap();
+            if search.is_some() {
+                return Err(MiningError::Busy);
+            }
+            search.insert(Arc::new(AtomicBool::new(false))).clone()
+        };
+        let result = self.search(&cancel, stop);
+        *self.search.lock().unwrap() = None;
+        result
+    }
+
+    /// Stops the running search, if any.
+    pub fn cancel(&self) {
+        if let Some(cancel) = &*self.search.lock().unwrap() {
+            cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn is_mining(&self) -> bool {
+        self.search.lock().unwrap().is_some()
+    }
+
+    /// Hash rate of the running search, or of the last one when idle.
+    pub fn hash_rate(&self) -> f64 {
+        match *self.started.lock().unwrap() {
+            Some(started) => MiningStats {
+                hashes: self.hashes.load(Ordering::Relaxed),
+                elapsed: started.elapsed(),
+            }
+            .hash_rate(),
+            None => self.last.lock().unwrap().hash_rate(),
+        }
+    }
+
+    fn search(&self, cancel: &AtomicBool, stop: &AtomicBool) -> Result<MinedBlock, MiningError> {
+        // subscribing with the same lock as the template makes every later block a new tip
+        let (template, mut events) = {
+            let mut chain = self.chain.write().unwrap();
+            (chain.block_template(), chain.subscribe())
+        };
+
+        self.hashes.store(0, Ordering::Relaxed);
+        let started = Instant::now();
+        *self.started.lock().unwrap() = Some(started);
+
+        let done = AtomicBool::new(false);
+        let solved = thread::scope(|s| {
+            s.spawn(|| {
+                let cancel_search = || cancel.store(true, Ordering::SeqCst);
+                while !done.load(Ordering::SeqCst) {
+                    if stop.load(Ordering::SeqCst) {
+                        cancel_search();
+                    }
+                    match events.try_recv() {
+                        Ok(ChainEvent::NewBlock(_)) | Ok(ChainEvent::Reorg { .. }) => {
+                            cancel_search()
+                        }
+                        Ok(ChainEvent::NewTransaction(_)) => {}
+                        Err(TryRecvError::Empty) => thread::sleep(TIP_POLL),
+                        Err(TryRecvError::Lagged(_)) => cancel_search(),
+                        Err(TryRecvError::Closed) => break,
+                    }
+                }
+            });
+            let solved = solve(&template, self.threads, cancel, &self.hashes);
+            done.store(true, Ordering::SeqCst);
+            solved
+        });
+
+        let stats = MiningStats {
+            hashes: self.hashes.load(Ordering::Relaxed),
+            elapsed: started.elapsed(),
+        };
+        *self.last.lock().unwrap() = stats;
+        *self.started.lock().unwrap() = None;
+        let block = solved.ok_or(MiningError::Cancelled)?;
+
+        let mut chain = self.chain.write().unwrap();
+        if stop.load(Ordering::SeqCst) || chain.last_block().hash() != block.header.previous_hash {
+            return Err(MiningError::Cancelled);
+        }
+        chain
+            .add_block(block.clone())
+            .map_err(MiningError::Rejected)?;
+        println!(
+            "mined block {} in {:?} at {:.0} hashes/s",
+            chain.len() - 1,
+            stats.elapsed,
+            stats.hash_rate()
+        );
+        Ok(MinedBlock {
+            height: chain.len() - 1,
+            block,
+            stats,
+        })
+    }
+}
+
+/// Searches the nonce of `block` on `threads` threads until its hash meets its target,
+/// `None` once `cancel` is set. Thread `i` tries the nonces `i`, `i + threads`, ... and
+/// moves its time stamp on when it runs out of them. Every hash is counted in `hashes`.
+pub fn solve(
+    block: &Block,
+    threads: usize,
+    cancel: &AtomicBool,
+    hashes: &AtomicU64,
+) -> Option<Block> {
+    let threads = threads.clamp(1, i32::MAX as usize);
+    let found = AtomicBool::new(false);
+    let solution = Mutex::new(None);
+
+    thread::scope(|s| {
+        for first in 0..threads {
+            let (found, solution) = (&found, &solution);
+            s.spawn(move || {
+                let mut candidate = block.clone();
+                candidate.header.nonce = first as i32;
+                let mut tried: u64 = 0;
+                loop {
+                    if tried == CHECK_EVERY {
+                        hashes.fetch_add(tried, Ordering::Relaxed);
+                        tried = 0;
+                        if found.load(Ordering::SeqCst) || cancel.load(Ordering::SeqCst) {
+                            return;
+                        }
+                    }
+
+                    tried += 1;
+                    if difficulty::meets_target(&candidate.hash(), candidate.header.target) {
+                        hashes.fetch_add(tried, Ordering::Relaxed);
+                        if !found.swap(true, Ordering::SeqCst) {
+                            *solution.lock().unwrap() = Some(candidate);
+                        }
+                        return;
+                    }
+
+                    match candidate.header.nonce.checked_add(threads as i32) {
+                        Some(nonce) => candidate.header.nonce = nonce,
+                        None => {
+                            candidate.header.nonce = first as i32;
+                            candidate.header.time_stamp += 1;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    solution.into_inner().unwrap()
+}
@@ -1,2 +1,4 @@
 pub mod blockchain;
+pub mod miner;
+pub mod storage;
 pub mod wallet;
@@ -0,0 +1,274 @@
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::Serialization;
+use crate::core::storage::ChainStore;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const BLOCKS_FILE: &str = "blocks.dat";
+const INDEX_FILE: &str = "blocks.idx";
+const POOL_FILE: &str = "pool.dat";
+
+const MAGIC: &[u8; 4] = b"BLKS";
+const VERSION: u32 = 12;
+const HEADER_LEN: u64 = 8;
+const CHECKSUM_LEN: usize = 4;
+
+/// Append-only block file plus an offset index, kept in a data directory.
+///
+/// `blocks.dat` starts with a magic and version header followed by one record per block:
+/// `[payload length: u64][payload][first 4 bytes of sha256(payload)]`.
+/// `blocks.idx` holds the offset of every record as a big endian u64 and is rebuilt
+/// from the block file whenever the two disagree.
+#[derive(Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+    blocks: File,
+    index: File,
+    offsets: Vec<u64>,
+}
+
+impl FileStore {
+    /// Opens the store in `dir`, creating it if needed and recovering from a crash
+    /// that left a half-written block at the end of the block file.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut blocks = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(BLOCKS_FILE))?;
+        FileStore::check_header(&mut blocks)?;
+
+        let offsets = FileStore::recover(&mut blocks)?;
+
+        let mut index = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(INDEX_FILE))?;
+        FileStore::sync_index(&mut index, &offsets)?;
+
+        Ok(FileStore {
+            dir,
+            blocks,
+            index,
+            offsets,
+        })
+    }
+
+    fn check_header(blocks: &mut File) -> io::Result<()> {
+        if blocks.metadata()?.len() < HEADER_LEN {
+            // a fresh file, or one that crashed before its header was complete
+            blocks.set_len(0)?;
+            blocks.seek(SeekFrom::Start(0))?;
+            blocks.write_all(MAGIC)?;
+            blocks.write_all(&VERSION.to_be_bytes())?;
+            return blocks.sync_all();
+        }
+
+        let mut header = [0_u8; HEADER_LEN as usize];
+        blocks.seek(SeekFrom::Start(0))?;
+        blocks.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "block file has an unknown format",
+            ));
+        }
+
+        let version = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported block file version {}", version),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Scans every record and truncates the file after the last complete one.
+    fn recover(blocks: &mut File) -> io::Result<Vec<u64>> {
+        let mut bytes = Vec::new();
+        blocks.seek(SeekFrom::Start(0))?;
+        blocks.read_to_end(&mut bytes)?;
+
+        let mut offsets = Vec::new();
+        let mut pos = HEADER_LEN as usize;
+        while pos < bytes.len() {
+            let Some(len_bytes) = bytes.get(pos..pos + 8) else {
+                break;
+            };
+            let len = u64::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+            let payload_start = pos + 8;
+            let Some(end) = payload_start
+                .checked_add(len)
+                .and_then(|n| n.checked_add(CHECKSUM_LEN))
+                .filter(|n| *n <= bytes.len())
+            else {
+                break;
+            };
+
+            let payload = &bytes[payload_start..payload_start + len];
+            if bytes[end - CHECKSUM_LEN..end] != checksum(payload) {
+                break;
+            }
+
+            offsets.push(pos as u64);
+            pos = end;
+        }
+
+        if pos < bytes.len() {
+            println!(
+                "block file ends with a partial record, dropping {} bytes",
+                bytes.len() - pos
+            );
+            blocks.set_len(pos as u64)?;
+            blocks.sync_all()?;
+        }
+
+        Ok(offsets)
+    }
+
+    fn sync_index(index: &mut File, offsets: &[u64]) -> io::Result<()> {
+        let expected: Vec<u8> = offsets.iter().flat_map(|o| o.to_be_bytes()).collect();
+        let mut current = Vec::new();
+        index.seek(SeekFrom::Start(0))?;
+        index.read_to_end(&mut current)?;
+
+        if current != expected {
+            index.set_len(0)?;
+            index.seek(SeekFrom::Start(0))?;
+            index.write_all(&expected)?;
+            index.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the block at `height` through the index. Fails with `InvalidData` when the
+    /// record does not parse as a block.
+    pub fn read_block(&mut self, height: usize) -> io::Result<Option<Block>> {
+        let Some(&offset) = self.offsets.get(height) else {
+            return Ok(None);
+        };
+
+        let mut len_bytes = [0_u8; 8];
+        self.blocks.seek(SeekFrom::Start(offset))?;
+        self.blocks.read_exact(&mut len_bytes)?;
+        let mut payload = vec![0_u8; u64::from_be_bytes(len_bytes) as usize];
+        self.blocks.read_exact(&mut payload)?;
+
+        // the checksum only says the record is whole, not that it holds a block
+        Block::try_deserialization(&payload)
+            .map(Some)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("block file record {} does not hold a block", height),
+                )
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+}
+
+impl ChainStore for FileStore {
+    fn load_blocks(&mut self) -> io::Result<Vec<Block>> {
+        let mut blocks = Vec::with_capacity(self.offsets.len());
+        for height in 0..self.offsets.len() {
+            if let Some(block) = self.read_block(height)? {
+                blocks.push(block);
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    fn append_block(&mut self, block: &Block) -> io::Result<()> {
+        let payload = block.serialization();
+        let mut record = Vec::with_capacity(8 + payload.len() + CHECKSUM_LEN);
+        record.extend((payload.len() as u64).to_be_bytes());
+        record.extend(&payload);
+        record.extend(checksum(&payload));
+
+        let offset = self.blocks.seek(SeekFrom::End(0))?;
+        self.blocks.write_all(&record)?;
+        self.blocks.sync_data()?;
+
+        self.index.seek(SeekFrom::End(0))?;
+        self.index.write_all(&offset.to_be_bytes())?;
+        self.index.sync_data()?;
+
+        self.offsets.push(offset);
+        Ok(())
+    }
+
+    fn truncate(&mut self, len: usize) -> io::Result<()> {
+        let Some(&end) = self.offsets.get(len) else {
+            return Ok(());
+        };
+
+        self.blocks.set_len(end)?;
+        self.blocks.sync_all()?;
+        self.index.set_len(len as u64 * 8)?;
+        self.index.sync_all()?;
+
+        self.offsets.truncate(len);
+        Ok(())
+    }
+
+    fn load_pool(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        let bytes = match fs::read(self.dir.join(POOL_FILE)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut pool = Vec::new();
+        let mut pos = 0;
+        while let Some(len_bytes) = bytes.get(pos..pos + 8) {
+            let len = u64::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+            pos += 8;
+            let Some(tx) = bytes.get(pos..pos + len) else {
+                break;
+            };
+            pool.push(tx.to_vec());
+            pos += len;
+        }
+
+        Ok(pool)
+    }
+
+    fn save_pool(&mut self, pool: &[Vec<u8>]) -> io::Result<()> {
+        let mut bin = Vec::new();
+        for tx in pool {
+            bin.extend((tx.len() as u64).to_be_bytes());
+            bin.extend(tx);
+        }
+
+        // write a temporary file first so a crash never leaves a torn pool behind
+        let tmp_path = self.dir.join(format!("{}.tmp", POOL_FILE));
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(&bin)?;
+        tmp.sync_all()?;
+        fs::rename(tmp_path, self.dir.join(POOL_FILE))
+    }
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = Sha256::digest(payload);
+    hash[0..CHECKSUM_LEN].try_into().unwrap()
+}
@@ -0,0 +1,46 @@
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::Serialization;
+use crate::core::storage::ChainStore;
+use std::io;
+
+/// Volatile store, everything is lost when it is dropped.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    blocks: Vec<Vec<u8>>,
+    pool: Vec<Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ChainStore for MemoryStore {
+    fn load_blocks(&mut self) -> io::Result<Vec<Block>> {
+        Ok(self
+            .blocks
+            .iter()
+            .map(|b| Block::deserialization(b.clone()))
+            .collect())
+    }
+
+    fn append_block(&mut self, block: &Block) -> io::Result<()> {
+        self.blocks.push(block.serialization());
+        Ok(())
+    }
+
+    fn truncate(&mut self, len: usize) -> io::Result<()> {
+        self.blocks.truncate(len);
+        Ok(())
+    }
+
+    fn load_pool(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        Ok(self.pool.clone())
+    }
+
+    fn save_pool(&mut self, pool: &[Vec<u8>]) -> io::Result<()> {
+        self.pool = pool.to_vec();
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use crate::core::blockchain::block::Block;
+use std::fmt::Debug;
+use std::io;
+
+pub mod file;
+pub mod memory;
+
+pub use file::FileStore;
+pub use memory::MemoryStore;
+
+/// Durable backing store for the blocks and pending transactions of a `BlockChain`.
+pub trait ChainStore: Debug + Send + Sync {
+    /// Returns every persisted block in chain order.
+    fn load_blocks(&mut self) -> io::Result<Vec<Block>>;
+
+    /// Appends a block to the end of the chain; it must be durable once this returns.
+    fn append_block(&mut self, block: &Block) -> io::Result<()>;
+
+    /// Drops every block from height `len` onwards.
+    fn truncate(&mut self, len: usize) -> io::Result<()>;
+
+    /// Returns the persisted transaction pool.
+    fn load_pool(&mut self) -> io::Result<Vec<Vec<u8>>>;
+
+    /// Replaces the persisted transaction pool.
+    fn save_pool(&mut self, pool: &[Vec<u8>]) -> io::Result<()>;
+}
@@ -0,0 +1,200 @@
+use crate::core::wallet::scheme::Scheme;
+use ripemd160::{Digest as RipDigest, Ripemd160};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+const HASH_LEN: usize = 20;
+const CHECKSUM_LEN: usize = 4;
+/// Version byte, scheme byte, key hash and checksum.
+const ADDRESS_LEN: usize = 2 + HASH_LEN + CHECKSUM_LEN;
+
+/// Chain an address belongs to, told apart by the version byte leading the address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// Addresses starting with `1`.
+    #[default]
+    Mainnet,
+    /// Addresses starting with `4`.
+    Testnet,
+}
+
+impl Network {
+    pub fn version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet => 0x6f,
+        }
+    }
+
+    /// Id signed into every transaction of the network, so one signed for a network is
+    /// never valid on another.
+    pub fn chain_id(self) -> u32 {
+        match self {
+            Network::Mainnet => 1,
+            Network::Testnet => 2,
+        }
+    }
+
+    fn from_version(version: u8) -> Option<Self> {
+        [Network::Mainnet, Network::Testnet]
+            .into_iter()
+            .find(|network| network.version() == version)
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => Err(format!("unknown network: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Testnet => write!(f, "testnet"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    /// The text has characters outside the base58 alphabet.
+    NotBase58,
+    /// The decoded address has this many bytes instead of 26.
+    Length(usize),
+    /// The check bytes do not match, the address was mistyped.
+    Checksum,
+    /// No network uses this version byte.
+    UnknownVersion(u8),
+    /// No signature scheme has this tag.
+    UnknownScheme(u8),
+    /// The address is valid but belongs to another network.
+    WrongNetwork { expected: Network, found: Network },
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::NotBase58 => write!(f, "address is not base58"),
+            AddressError::Length(len) => {
+                write!(f, "address has {} bytes instead of {}", len, ADDRESS_LEN)
+            }
+            AddressError::Checksum => write!(f, "address checksum does not match"),
+            AddressError::UnknownVersion(version) => {
+                write!(f, "unknown address version {:#04x}", version)
+            }
+            AddressError::UnknownScheme(tag) => {
+                write!(f, "unknown signature scheme {:#04x}", tag)
+            }
+            AddressError::WrongNetwork { expected, found } => {
+                write!(f, "{} address on {}", found, expected)
+            }
+        }
+    }
+}
+
+/// The hash of a public key with its signature scheme and the network it is used on.
+/// Written in base58 as the version byte of the network, the tag of the scheme, the
+/// RIPEMD-160 of the SHA-256 of the key, and the first four bytes of the double SHA-256
+/// of all three, so a mistyped address is caught.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    network: Network,
+    scheme: Scheme,
+    hash: [u8; HASH_LEN],
+}
+
+impl Address {
+    /// Address on `network` of the public key `key` of `scheme`.
+    pub fn from_public_key(network: Network, scheme: Scheme, key: &[u8]) -> Self {
+        let hash = Ripemd160::digest(&Sha256::digest(key));
+        Address {
+            network,
+            scheme,
+            hash: hash.into(),
+        }
+    }
+
+    /// Reads `address` and checks that it belongs to `network`.
+    pub fn validate(address: &str, network: Network) -> Result<Self, AddressError> {
+        let address: Address = address.parse()?;
+        if address.network != network {
+            return Err(AddressError::WrongNetwork {
+                expected: network,
+                found: address.network,
+            });
+        }
+        Ok(address)
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Scheme of the key the address belongs to, the one its transactions are signed with.
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// RIPEMD-160 of the SHA-256 of the public key.
+    pub fn hash(&self) -> &[u8; HASH_LEN] {
+        &self.hash
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(ADDRESS_LEN);
+        bytes.push(self.network.version());
+        bytes.push(self.scheme.tag());
+        bytes.extend_from_slice(&self.hash);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bs58::encode(bytes).into_string()
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    /// Decodes an address of any network, checking its length, checksum, version and
+    /// scheme.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s)
+            .into_vec()
+            .map_err(|_| AddressError::NotBase58)?;
+        if bytes.len() != ADDRESS_LEN {
+            return Err(AddressError::Length(bytes.len()));
+        }
+
+        let (payload, check) = bytes.split_at(2 + HASH_LEN);
+        if checksum(payload) != check {
+            return Err(AddressError::Checksum);
+        }
+        let network =
+            Network::from_version(payload[0]).ok_or(AddressError::UnknownVersion(payload[0]))?;
+        let scheme = Scheme::from_tag(payload[1]).ok_or(AddressError::UnknownScheme(payload[1]))?;
+        Ok(Address {
+            network,
+            scheme,
+            hash: payload[2..].try_into().unwrap(),
+        })
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::digest(Sha256::digest(payload));
+    digest[..CHECKSUM_LEN].try_into().unwrap()
+}
@@ -0,0 +1,265 @@
+use crate::core::blockchain::BlockChain;
+use crate::core::wallet::address::Network;
+use crate::core::wallet::scheme::PrivateKey;
+use crate::core::wallet::Wallet;
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use p256::ecdsa::SigningKey;
+use p256::elliptic_curve::PrimeField;
+use p256::{FieldBytes, NonZeroScalar, Scalar};
+use rand_core::{OsRng, RngCore};
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Key of the HMAC turning a seed into the master key, the one SLIP-0010 gives P-256.
+const MASTER_SECRET: &[u8] = b"Nist256p1 seed";
+/// Child numbers from this one on are hardened, derived from the private key alone.
+pub const HARDENED: u32 = 1 << 31;
+/// Path of the receiving addresses of the first account, address `i` is its child `i`.
+pub const RECEIVE_PATH: &str = "m/44'/0'/0'/0";
+/// Unused addresses in a row after which a scan gives up, as in BIP-44.
+pub const GAP_LIMIT: u32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HdError {
+    /// The phrase is not a valid BIP-39 mnemonic.
+    Mnemonic(String),
+    /// The text is not a derivation path like `m/44'/0'/0'/0/1`.
+    Path(String),
+}
+
+impl fmt::Display for HdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HdError::Mnemonic(reason) => write!(f, "invalid mnemonic: {}", reason),
+            HdError::Path(path) => write!(f, "invalid derivation path: {}", path),
+        }
+    }
+}
+
+/// Child numbers leading from the master key to a key, hardened ones at or past `HARDENED`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The path one level down, to child `index`.
+    pub fn child(&self, index: u32) -> Self {
+        let mut path = self.0.clone();
+        path.push(index);
+        DerivationPath(path)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = HdError;
+
+    /// Reads `m` followed by `/`-separated child numbers, hardened ones marked with `'`
+    /// or `h`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(HdError::Path(s.to_string()));
+        }
+        parts
+            .map(|part| {
+                let (number, hardened) = match part.strip_suffix(['\'', 'h']) {
+                    Some(number) => (number, true),
+                    None => (part, false),
+                };
+                match number.parse::<u32>() {
+                    Ok(n) if n < HARDENED => Ok(if hardened { n + HARDENED } else { n }),
+                    _ => Err(HdError::Path(s.to_string())),
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for &index in &self.0 {
+            if index >= HARDENED {
+                write!(f, "/{}'", index - HARDENED)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A private key with the chain code its children are derived with, following SLIP-0010
+/// for P-256: BIP-32 with the curve of the wallets.
+#[derive(Clone)]
+pub struct ExtendedKey {
+    key: NonZeroScalar,
+    chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    /// Master key of `seed`.
+    pub fn master(seed: &[u8]) -> Self {
+        let mut digest = hmac_sha512(MASTER_SECRET, &[seed]);
+        loop {
+            let (left, right) = digest.split_at(32);
+            if let Some(key) = parse_scalar(left).and_then(|k| NonZeroScalar::new(k).into()) {
+                return ExtendedKey {
+                    key,
+                    chain_code: right.try_into().unwrap(),
+                };
+            }
+            // happens with odds of 2^-127, SLIP-0010 hashes again
+            digest = hmac_sha512(MASTER_SECRET, &[&digest]);
+        }
+    }
+
+    /// Child `index` of this key, hardened from `HARDENED` on.
+    pub fn child(&self, index: u32) -> Self {
+        let parent = SigningKey::from(self.key);
+        let mut digest = if index >= HARDENED {
+            hmac_sha512(
+                &self.chain_code,
+                &[&[0], &parent.to_bytes(), &index.to_be_bytes()],
+            )
+        } else {
+            let point = parent.verifying_key().to_encoded_point(true);
+            hmac_sha512(&self.chain_code, &[point.as_bytes(), &index.to_be_bytes()])
+        };
+        loop {
+            let (left, right) = digest.split_at(32);
+            let key =
+                parse_scalar(left).and_then(|tweak| NonZeroScalar::new(tweak + *self.key).into());
+            if let Some(key) = key {
+                return ExtendedKey {
+                    key,
+                    chain_code: right.try_into().unwrap(),
+                };
+            }
+            // the next candidate SLIP-0010 defines for a tweak past the order or a zero key
+            digest = hmac_sha512(&self.chain_code, &[&[1], right, &index.to_be_bytes()]);
+        }
+    }
+
+    /// The key at `path` below this one.
+    pub fn derive(&self, path: &DerivationPath) -> Self {
+        path.0
+            .iter()
+            .fold(self.clone(), |key, &index| key.child(index))
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// The wallet signing with this key, its address on `network`.
+    pub fn wallet(&self, network: Network) -> Wallet {
+        Wallet::from_private_key(PrivateKey::P256(SigningKey::from(self.key)), network)
+    }
+}
+
+/// P-256 wallets derived from one seed, so a single mnemonic backs all of them up.
+pub struct HdWallet {
+    master: ExtendedKey,
+    /// Parent of the receiving addresses, derived once.
+    receive: ExtendedKey,
+    network: Network,
+}
+
+impl HdWallet {
+    /// A new random mnemonic of `word_count` words, 12, 15, 18, 21 or 24.
+    pub fn generate_mnemonic(word_count: usize) -> Result<Mnemonic, HdError> {
+        if ![12, 15, 18, 21, 24].contains(&word_count) {
+            return Err(HdError::Mnemonic(format!(
+                "{} words instead of 12, 15, 18, 21 or 24",
+                word_count
+            )));
+        }
+        // every 3 words carry 32 bits of entropy and one checksum bit
+        let mut entropy = vec![0_u8; word_count / 3 * 4];
+        OsRng.fill_bytes(&mut entropy);
+        Mnemonic::from_entropy(&entropy).map_err(|e| HdError::Mnemonic(e.to_string()))
+    }
+
+    /// Wallets on `network` of the seed of `mnemonic`, salted with `passphrase` which may
+    /// be empty.
+    pub fn from_mnemonic(
+        mnemonic: &str,
+        passphrase: &str,
+        network: Network,
+    ) -> Result<Self, HdError> {
+        let mnemonic = Mnemonic::parse(mnemonic).map_err(|e| HdError::Mnemonic(e.to_string()))?;
+        Ok(HdWallet::from_seed(&mnemonic.to_seed(passphrase), network))
+    }
+
+    pub fn from_seed(seed: &[u8], network: Network) -> Self {
+        let master = ExtendedKey::master(seed);
+        let receive = master.derive(&RECEIVE_PATH.parse().unwrap());
+        HdWallet {
+            master,
+            receive,
+            network,
+        }
+    }
+
+    /// The wallet at `path` from the master key.
+    pub fn derive(&self, path: &DerivationPath) -> Wallet {
+        self.master.derive(path).wallet(self.network)
+    }
+
+    /// Receiving wallet number `index`.
+    pub fn wallet(&self, index: u32) -> Wallet {
+        self.receive.child(index).wallet(self.network)
+    }
+
+    /// Path of receiving wallet number `index`.
+    pub fn receive_path(index: u32) -> DerivationPath {
+        RECEIVE_PATH.parse::<DerivationPath>().unwrap().child(index)
+    }
+
+    /// Receiving wallets from number `start` on, with their numbers.
+    pub fn wallets(&self, start: u32) -> impl Iterator<Item = (u32, Wallet)> + '_ {
+        (start..HARDENED).map(|index| (index, self.wallet(index)))
+    }
+
+    /// Receiving wallets with transactions on `chain` or waiting for a block, looked for
+    /// in order until `gap_limit` in a row have none.
+    pub fn scan(&self, chain: &BlockChain, gap_limit: u32) -> Vec<(u32, Wallet)> {
+        let mut used = Vec::new();
+        let mut gap = 0;
+        for (index, wallet) in self.wallets(0) {
+            if gap >= gap_limit {
+                break;
+            }
+            if chain
+                .address_history(&wallet.get_address())
+                .next()
+                .is_some()
+            {
+                used.push((index, wallet));
+                gap = 0;
+            } else {
+                gap += 1;
+            }
+        }
+        used
+    }
+}
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> [u8; 64] {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC takes keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// Big endian scalar of 32 bytes, `None` at or past the order of the curve.
+fn parse_scalar(bytes: &[u8]) -> Option<Scalar> {
+    let bytes: [u8; 32] = bytes.try_into().ok()?;
+    Scalar::from_repr(FieldBytes::from(bytes)).into()
+}
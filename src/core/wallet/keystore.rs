@@ -0,0 +1,272 @@
+use crate::core::wallet::address::{Address, AddressError};
+use crate::core::wallet::scheme::PrivateKey;
+use crate::core::wallet::Wallet;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const VERSION: u32 = 1;
+const CIPHER: &str = "aes-256-gcm";
+const KDF: &str = "scrypt";
+/// Cost of deriving the key from a password: 2^15 rounds over 32 MiB.
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+#[derive(Debug)]
+pub enum KeystoreError {
+    Io(io::Error),
+    /// No wallet with this address is stored.
+    NotFound,
+    /// The password does not open the wallet.
+    WrongPassword,
+    /// The stored wallet is not in a format this version reads.
+    Malformed(String),
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::Io(e) => write!(f, "keystore is unreadable: {}", e),
+            KeystoreError::NotFound => write!(f, "wallet is not in the keystore"),
+            KeystoreError::WrongPassword => write!(f, "wrong password"),
+            KeystoreError::Malformed(reason) => write!(f, "malformed keystore file: {}", reason),
+        }
+    }
+}
+
+impl From<io::Error> for KeystoreError {
+    fn from(e: io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+/// A private key encrypted with a password, as stored in a keystore file. The key is
+/// sealed with AES-256-GCM under a key derived from the password with scrypt, and the
+/// address is authenticated along with it so a file cannot be passed off as another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedKey {
+    pub version: u32,
+    pub address: String,
+    pub public_key: String,
+    pub crypto: KeyCipher,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyCipher {
+    pub cipher: String,
+    /// Hex of the encrypted key followed by its authentication tag.
+    pub ciphertext: String,
+    pub nonce: String,
+    pub kdf: String,
+    pub kdf_params: KdfParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+impl EncryptedKey {
+    /// Encrypts the private key of `wallet` with `password`, under a fresh salt and nonce.
+    pub fn encrypt(wallet: &Wallet, password: &str) -> Self {
+        let mut salt = [0_u8; SALT_LEN];
+        let mut nonce = [0_u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let kdf_params = KdfParams {
+            log_n: SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: hex::encode(salt),
+        };
+
+        let address = wallet.get_address();
+        let cipher = derive_cipher(password, &salt, &kdf_params)
+            .expect("the default scrypt parameters are valid");
+        let ciphertext = cipher
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &wallet.private_key().to_bytes(),
+                    aad: address.as_bytes(),
+                },
+            )
+            .expect("a 32 byte key always encrypts");
+
+        EncryptedKey {
+            version: VERSION,
+            public_key: wallet.public_key_str(),
+            address,
+            crypto: KeyCipher {
+                cipher: CIPHER.to_string(),
+                ciphertext: hex::encode(ciphertext),
+                nonce: hex::encode(nonce),
+                kdf: KDF.to_string(),
+                kdf_params,
+            },
+        }
+    }
+
+    /// Opens the key with `password` and rebuilds its wallet.
+    pub fn decrypt(&self, password: &str) -> Result<Wallet, KeystoreError> {
+        let crypto = &self.crypto;
+        if self.version != VERSION || crypto.cipher != CIPHER || crypto.kdf != KDF {
+            return Err(KeystoreError::Malformed(format!(
+                "unsupported version {} with {} and {}",
+                self.version, crypto.cipher, crypto.kdf
+            )));
+        }
+        let field = |name: &str, value: &str| {
+            hex::decode(value).map_err(|_| KeystoreError::Malformed(format!("{} is not hex", name)))
+        };
+        let salt = field("salt", &crypto.kdf_params.salt)?;
+        let nonce = field("nonce", &crypto.nonce)?;
+        let ciphertext = field("ciphertext", &crypto.ciphertext)?;
+        let nonce: [u8; NONCE_LEN] = nonce
+            .try_into()
+            .map_err(|_| KeystoreError::Malformed("nonce has the wrong length".to_string()))?;
+
+        // a file may not ask for more work than this version ever writes, or opening it
+        // could take as long and as much memory as it likes
+        let params = &crypto.kdf_params;
+        if params.log_n > SCRYPT_LOG_N || params.r > SCRYPT_R || params.p > SCRYPT_P {
+            return Err(KeystoreError::Malformed(
+                "scrypt parameters are above the supported cost".to_string(),
+            ));
+        }
+        let cipher = derive_cipher(password, &salt, params).ok_or(KeystoreError::Malformed(
+            "invalid scrypt parameters".to_string(),
+        ))?;
+        let key = cipher
+            .decrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: self.address.as_bytes(),
+                },
+            )
+            .map_err(|_| KeystoreError::WrongPassword)?;
+        // the address tells the scheme of the key and the network it is used on
+        let address: Address = self
+            .address
+            .parse()
+            .map_err(|e: AddressError| KeystoreError::Malformed(e.to_string()))?;
+        let private_key = PrivateKey::from_bytes(address.scheme(), &key)
+            .ok_or(KeystoreError::Malformed("not a private key".to_string()))?;
+
+        let wallet = Wallet::from_private_key(private_key, address.network());
+        if wallet.get_address() != self.address {
+            return Err(KeystoreError::Malformed(
+                "address does not belong to the key".to_string(),
+            ));
+        }
+        Ok(wallet)
+    }
+}
+
+fn derive_cipher(password: &str, salt: &[u8], params: &KdfParams) -> Option<Aes256Gcm> {
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, KEY_LEN).ok()?;
+    let mut key = [0_u8; KEY_LEN];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key).ok()?;
+    Aes256Gcm::new_from_slice(&key).ok()
+}
+
+/// Directory of encrypted wallets, one `<address>.json` file each.
+#[derive(Debug)]
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    /// Opens the keystore in `dir`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Keystore { dir })
+    }
+
+    /// Addresses of the stored wallets, sorted.
+    pub fn list(&self) -> io::Result<Vec<String>> {
+        let mut addresses = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                if let Some(address) = path.file_stem().and_then(|s| s.to_str()) {
+                    addresses.push(address.to_string());
+                }
+            }
+        }
+        addresses.sort();
+        Ok(addresses)
+    }
+
+    pub fn read(&self, address: &str) -> Result<EncryptedKey, KeystoreError> {
+        let path = self.path(address).ok_or(KeystoreError::NotFound)?;
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(KeystoreError::NotFound),
+            Err(e) => return Err(KeystoreError::Io(e)),
+        };
+        let key: EncryptedKey =
+            serde_json::from_str(&json).map_err(|e| KeystoreError::Malformed(e.to_string()))?;
+        if key.address != address {
+            return Err(KeystoreError::Malformed(
+                "file name does not match its address".to_string(),
+            ));
+        }
+        Ok(key)
+    }
+
+    /// Stores `key`, replacing the file of its address if there is one.
+    pub fn write(&self, key: &EncryptedKey) -> Result<(), KeystoreError> {
+        let path = self.path(&key.address).ok_or(KeystoreError::Malformed(
+            "address is not base58".to_string(),
+        ))?;
+        let json =
+            serde_json::to_vec_pretty(key).map_err(|e| KeystoreError::Malformed(e.to_string()))?;
+
+        // write a temporary file first so a crash never leaves a torn key behind
+        let tmp_path = path.with_extension("json.tmp");
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(&json)?;
+        tmp.sync_all()?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Encrypts the wallet of `address` again under `new_password`.
+    pub fn change_password(
+        &self,
+        address: &str,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), KeystoreError> {
+        let wallet = self.read(address)?.decrypt(old_password)?;
+        self.write(&EncryptedKey::encrypt(&wallet, new_password))
+    }
+
+    /// File of `address`, `None` for anything but a base58 address so no other path can
+    /// be reached through it.
+    fn path(&self, address: &str) -> Option<PathBuf> {
+        let base58 = !address.is_empty()
+            && address
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() && !b"0OIl".contains(&b));
+        base58.then(|| self.dir.join(format!("{}.json", address)))
+    }
+}
@@ -1,132 +1,186 @@
-use p256::ecdsa::{
-    signature::{Signer, Verifier},
-    Signature, SigningKey, VerifyingKey,
-};
-use rand_core::OsRng;
-use ripemd160::{Digest as RipDigest, Ripemd160};
-use serde::Serialize;
-use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+
+pub mod address;
+pub mod hd;
+pub mod keystore;
+pub mod scheme;
+pub mod signing;
+
+use address::{Address, Network};
+use keystore::{EncryptedKey, Keystore, KeystoreError};
+use scheme::{PrivateKey, Scheme};
 
 pub struct Wallet {
-    pub signing_key: SigningKey,
-    pub verifying_key: VerifyingKey,
+    private_key: PrivateKey,
     address: String,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WalletTransaction {
     pub sender: String,
     pub recipient: String,
     pub amount: u64,
+    /// Paid to the miner of the block including the transaction.
+    pub fee: u64,
+    /// Number of transfers the sender made before this one, so it can be included once.
+    pub nonce: u64,
+    /// Scheme of the key of the sender, the public key and signature are checked with it.
+    #[serde(default)]
+    pub scheme: Scheme,
     pub public_key: String,
     pub signature: String,
 }
 
+impl Default for Wallet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Wallet {
+    /// A P-256 wallet with a random key, its address on the default network.
     pub fn new() -> Self {
-        let signing_key = SigningKey::random(&mut OsRng);
-        let verifying_key = signing_key.verifying_key().clone();
-        let mut address = String::new();
-        let mut gen_address = || {
-            let key_points = verifying_key.to_encoded_point(false);
-
-            if let (Some(x), Some(y)) = (key_points.x(), key_points.y()) {
-                let mut pub_key_bytes = Vec::with_capacity(x.len() + y.len());
-                pub_key_bytes.extend_from_slice(x);
-                pub_key_bytes.extend_from_slice(y);
-                let hash = Sha256::digest(&pub_key_bytes);
-                let mut hasher = Ripemd160::new();
-                hasher.update(&hash);
-                let mut hash_result = hasher.finalize().to_vec();
-                hash_result.insert(0, 0x00);
-                let checksum = &hash[0..4];
-                let full_hash = [hash_result, checksum.to_vec()].concat();
-                address = bs58::encode(full_hash).into_string();
-            }
-        };
+        Wallet::with_network(Network::default())
+    }
 
-        gen_address();
+    /// A P-256 wallet with a random key, its address on `network`.
+    pub fn with_network(network: Network) -> Self {
+        Wallet::generate(Scheme::default(), network)
+    }
+
+    /// A wallet with a random key of `scheme`, its address on `network`.
+    pub fn generate(scheme: Scheme, network: Network) -> Self {
+        Wallet::from_private_key(PrivateKey::generate(scheme), network)
+    }
 
+    pub fn from_private_key(private_key: PrivateKey, network: Network) -> Self {
+        let address =
+            Address::from_public_key(network, private_key.scheme(), &private_key.public_key())
+                .encode();
         Wallet {
-            signing_key,
-            verifying_key,
+            private_key,
             address,
         }
     }
 
-    pub fn new_from(public_key_str: &String, private_key_str: &String, address: &String) -> Self {
-        let mut public_key_bin = hex::decode(public_key_str).unwrap();
-        public_key_bin.insert(0, 0x04);
-        let verifying_key = VerifyingKey::from_sec1_bytes(&public_key_bin).unwrap();
+    /// Stores this wallet in `keystore`, its private key encrypted with `password`.
+    pub fn save(&self, keystore: &Keystore, password: &str) -> Result<(), KeystoreError> {
+        keystore.write(&EncryptedKey::encrypt(self, password))
+    }
 
-        let private_key_bytes = hex::decode(private_key_str).unwrap();
-        let private_key_bytes: [u8; 32] = private_key_bytes
-            .try_into()
-            .expect("Invalid private key hex data");
-        let signing_key = SigningKey::from_bytes((&private_key_bytes).into()).unwrap();
+    /// Opens the wallet of `address` stored in `keystore` with `password`.
+    pub fn load(keystore: &Keystore, address: &str, password: &str) -> Result<Self, KeystoreError> {
+        keystore.read(address)?.decrypt(password)
+    }
 
-        Wallet {
-            verifying_key,
-            signing_key,
-            address: address.clone(),
+    /// Address on `network` of a public key of `scheme` given in the hex form of
+    /// `public_key_str`, `None` if it is not a valid key.
+    pub fn address_from_public_key_str(
+        public_key_str: &str,
+        scheme: Scheme,
+        network: Network,
+    ) -> Option<String> {
+        let public_key = hex::decode(public_key_str).ok()?;
+        if !scheme::is_public_key(scheme, &public_key) {
+            return None;
         }
+        Some(Address::from_public_key(network, scheme, &public_key).encode())
     }
 
-    pub fn private_key_str(&self) -> String {
-        //convert the private key into hex string
-        hex::encode(self.signing_key.to_bytes())
+    /// Whether the public key of `transaction` is the one its sender address derives from,
+    /// with the scheme and on the network of that address. A valid signature alone only
+    /// proves the signer owns the embedded key.
+    pub fn is_signed_by_sender(transaction: &WalletTransaction) -> bool {
+        let Ok(sender) = transaction.sender.parse::<Address>() else {
+            return false;
+        };
+        sender.scheme() == transaction.scheme
+            && Wallet::address_from_public_key_str(
+                &transaction.public_key,
+                sender.scheme(),
+                sender.network(),
+            )
+            .as_deref()
+                == Some(transaction.sender.as_str())
+    }
+
+    /// Wallet of the hex private key of `scheme` signing for `address`, `None` if it is
+    /// not a key.
+    pub fn new_from(scheme: Scheme, private_key_str: &str, address: &str) -> Option<Self> {
+        let private_key = PrivateKey::from_bytes(scheme, &hex::decode(private_key_str).ok()?)?;
+        Some(Wallet {
+            private_key,
+            address: address.to_string(),
+        })
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.private_key.scheme()
+    }
+
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.private_key
     }
 
     pub fn public_key_str(&self) -> String {
-        let key_points = self.verifying_key.to_encoded_point(false);
-        if let (Some(x), Some(y)) = (key_points.x(), key_points.y()) {
-            let pub_str = hex::encode(x) + hex::encode(y).as_str();
-            pub_str
-        } else {
-            String::new()
-        }
+        hex::encode(self.private_key.public_key())
     }
 
     pub fn get_address(&self) -> String {
         self.address.clone()
     }
 
-    pub fn sign_transaction(&self, reciever: &String, amount: u64) -> WalletTransaction {
+    /// Signs a transfer of `amount` to `reciever` paying `fee` to the miner, `nonce` is
+    /// the number of transfers this wallet already made, confirmed or pending.
+    pub fn sign_transaction(
+        &self,
+        reciever: &str,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+    ) -> WalletTransaction {
         let mut transaction = WalletTransaction {
             sender: self.address.clone(),
-            recipient: reciever.clone(),
+            recipient: reciever.to_string(),
             amount,
+            fee,
+            nonce,
+            scheme: self.scheme(),
             signature: String::new(),
             public_key: self.public_key_str(),
         };
 
-        let serialized_str = serde_json::to_string(&transaction).unwrap();
-        let serialized = serialized_str.as_bytes();
-        let sig: Signature = self.signing_key.sign(serialized);
-        transaction.signature = hex::encode(sig.to_bytes());
+        // signed for the network of the sender, nodes of another network turn it down; a
+        // wallet of a mistyped address signs for the default one and its transfer is turned
+        // down as not coming from the sender
+        let network = self
+            .address
+            .parse::<Address>()
+            .map(|address| address.network())
+            .unwrap_or_default();
+        let payload = signing::signing_payload(&transaction, network.chain_id())
+            .expect("the public key of a wallet is hex");
+        transaction.signature = hex::encode(self.private_key.sign(&payload));
         transaction
     }
 
-    pub fn verify_transaction(transaction: &WalletTransaction) -> bool {
-        let signature_str = transaction.signature.clone();
-        let signature_bin = hex::decode(signature_str).unwrap();
-        let mut transaction_clone = transaction.clone();
-        transaction_clone.signature = String::new();
-
-        let serialized_str = serde_json::to_string(&transaction_clone).unwrap();
-        let serialized = serialized_str.as_bytes();
-
-        let sig_array: [u8; 64] = signature_bin.try_into().unwrap();
-
-        let signature = match Signature::from_bytes(&sig_array.into()) {
-            Ok(sig) => sig,
-            Err(_) => return false,
+    /// Whether the signature of `transaction` signs its payload for the chain of `network`,
+    /// the one of the node checking it. A sender of another network is turned down, what
+    /// was signed for one chain never passes on another.
+    pub fn verify_transaction(transaction: &WalletTransaction, network: Network) -> bool {
+        let (Ok(signature), Ok(public_key), Ok(sender)) = (
+            hex::decode(&transaction.signature),
+            hex::decode(&transaction.public_key),
+            transaction.sender.parse::<Address>(),
+        ) else {
+            return false;
         };
-
-        let public_key_str = transaction_clone.public_key.clone();
-        let mut public_key_bin = hex::decode(public_key_str).unwrap();
-        public_key_bin.insert(0, 0x04);
-        let public_key = VerifyingKey::from_sec1_bytes(&public_key_bin).unwrap();
-        public_key.verify(serialized, &signature).is_ok()
+        if sender.network() != network {
+            return false;
+        }
+        let Some(payload) = signing::signing_payload(transaction, network.chain_id()) else {
+            return false;
+        };
+        scheme::verify(transaction.scheme, &public_key, &payload, &signature)
     }
 }
@@ -0,0 +1,308 @@
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Signature scheme of a key, tagged in the transactions it signs and in its address so
+/// they are checked with the right curve.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    /// ECDSA over NIST P-256, with low-S signatures.
+    #[default]
+    P256,
+    /// ECDSA over secp256k1, with low-S signatures.
+    Secp256k1,
+    /// EdDSA over Curve25519.
+    Ed25519,
+}
+
+impl Scheme {
+    /// Byte standing for the scheme in addresses and on-chain transactions.
+    pub fn tag(self) -> u8 {
+        match self {
+            Scheme::P256 => 0,
+            Scheme::Secp256k1 => 1,
+            Scheme::Ed25519 => 2,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        [Scheme::P256, Scheme::Secp256k1, Scheme::Ed25519]
+            .into_iter()
+            .find(|scheme| scheme.tag() == tag)
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "p256" => Ok(Scheme::P256),
+            "secp256k1" => Ok(Scheme::Secp256k1),
+            "ed25519" => Ok(Scheme::Ed25519),
+            _ => Err(format!("unknown signature scheme: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scheme::P256 => write!(f, "p256"),
+            Scheme::Secp256k1 => write!(f, "secp256k1"),
+            Scheme::Ed25519 => write!(f, "ed25519"),
+        }
+    }
+}
+
+/// What a wallet needs from a signature scheme. Keys and signatures cross it as bytes:
+/// 32 byte private keys, the public key in the encoding its address hashes, and 64 byte
+/// signatures.
+pub trait SignatureScheme {
+    const SCHEME: Scheme;
+    type SigningKey: Clone;
+    type VerifyingKey;
+
+    fn generate() -> Self::SigningKey;
+    fn signing_key_from_bytes(bytes: &[u8]) -> Option<Self::SigningKey>;
+    fn signing_key_to_bytes(key: &Self::SigningKey) -> Vec<u8>;
+    fn verifying_key(key: &Self::SigningKey) -> Self::VerifyingKey;
+    fn encode_public_key(key: &Self::VerifyingKey) -> Vec<u8>;
+    fn decode_public_key(bytes: &[u8]) -> Option<Self::VerifyingKey>;
+    fn sign(key: &Self::SigningKey, message: &[u8]) -> Vec<u8>;
+    fn verify(key: &Self::VerifyingKey, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// ECDSA over P-256 with SHA-256, public keys as their `x || y` coordinates. Signatures
+/// are normalized to low S and only those verify, as for `Secp256k1`.
+pub struct P256;
+
+impl SignatureScheme for P256 {
+    const SCHEME: Scheme = Scheme::P256;
+    type SigningKey = p256::ecdsa::SigningKey;
+    type VerifyingKey = p256::ecdsa::VerifyingKey;
+
+    fn generate() -> Self::SigningKey {
+        p256::ecdsa::SigningKey::random(&mut OsRng)
+    }
+
+    fn signing_key_from_bytes(bytes: &[u8]) -> Option<Self::SigningKey> {
+        p256::ecdsa::SigningKey::from_slice(bytes).ok()
+    }
+
+    fn signing_key_to_bytes(key: &Self::SigningKey) -> Vec<u8> {
+        key.to_bytes().to_vec()
+    }
+
+    fn verifying_key(key: &Self::SigningKey) -> Self::VerifyingKey {
+        *key.verifying_key()
+    }
+
+    fn encode_public_key(key: &Self::VerifyingKey) -> Vec<u8> {
+        // drop the 0x04 leading an uncompressed point
+        key.to_encoded_point(false).as_bytes()[1..].to_vec()
+    }
+
+    fn decode_public_key(bytes: &[u8]) -> Option<Self::VerifyingKey> {
+        p256::ecdsa::VerifyingKey::from_sec1_bytes(&[&[0x04], bytes].concat()).ok()
+    }
+
+    fn sign(key: &Self::SigningKey, message: &[u8]) -> Vec<u8> {
+        use p256::ecdsa::signature::Signer;
+        let signature: p256::ecdsa::Signature = key.sign(message);
+        signature
+            .normalize_s()
+            .unwrap_or(signature)
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn verify(key: &Self::VerifyingKey, message: &[u8], signature: &[u8]) -> bool {
+        use p256::ecdsa::signature::Verifier;
+        p256::ecdsa::Signature::from_slice(signature).is_ok_and(|signature| {
+            // a high S has a low twin, only one of the two may pass
+            signature.normalize_s().is_none() && key.verify(message, &signature).is_ok()
+        })
+    }
+}
+
+/// ECDSA over secp256k1 with SHA-256, public keys as their `x || y` coordinates. Only
+/// low-S signatures verify, so a signature cannot be flipped into a second valid one.
+pub struct Secp256k1;
+
+impl SignatureScheme for Secp256k1 {
+    const SCHEME: Scheme = Scheme::Secp256k1;
+    type SigningKey = k256::ecdsa::SigningKey;
+    type VerifyingKey = k256::ecdsa::VerifyingKey;
+
+    fn generate() -> Self::SigningKey {
+        k256::ecdsa::SigningKey::random(&mut OsRng)
+    }
+
+    fn signing_key_from_bytes(bytes: &[u8]) -> Option<Self::SigningKey> {
+        k256::ecdsa::SigningKey::from_slice(bytes).ok()
+    }
+
+    fn signing_key_to_bytes(key: &Self::SigningKey) -> Vec<u8> {
+        key.to_bytes().to_vec()
+    }
+
+    fn verifying_key(key: &Self::SigningKey) -> Self::VerifyingKey {
+        *key.verifying_key()
+    }
+
+    fn encode_public_key(key: &Self::VerifyingKey) -> Vec<u8> {
+        key.to_encoded_point(false).as_bytes()[1..].to_vec()
+    }
+
+    fn decode_public_key(bytes: &[u8]) -> Option<Self::VerifyingKey> {
+        k256::ecdsa::VerifyingKey::from_sec1_bytes(&[&[0x04], bytes].concat()).ok()
+    }
+
+    fn sign(key: &Self::SigningKey, message: &[u8]) -> Vec<u8> {
+        use k256::ecdsa::signature::Signer;
+        let signature: k256::ecdsa::Signature = key.sign(message);
+        signature.to_bytes().to_vec()
+    }
+
+    fn verify(key: &Self::VerifyingKey, message: &[u8], signature: &[u8]) -> bool {
+        use k256::ecdsa::signature::Verifier;
+        k256::ecdsa::Signature::from_slice(signature)
+            .is_ok_and(|signature| key.verify(message, &signature).is_ok())
+    }
+}
+
+/// Ed25519, public keys in their 32 byte compressed form.
+pub struct Ed25519;
+
+impl SignatureScheme for Ed25519 {
+    const SCHEME: Scheme = Scheme::Ed25519;
+    type SigningKey = ed25519_dalek::SigningKey;
+    type VerifyingKey = ed25519_dalek::VerifyingKey;
+
+    fn generate() -> Self::SigningKey {
+        ed25519_dalek::SigningKey::generate(&mut OsRng)
+    }
+
+    fn signing_key_from_bytes(bytes: &[u8]) -> Option<Self::SigningKey> {
+        Some(ed25519_dalek::SigningKey::from_bytes(
+            bytes.try_into().ok()?,
+        ))
+    }
+
+    fn signing_key_to_bytes(key: &Self::SigningKey) -> Vec<u8> {
+        key.to_bytes().to_vec()
+    }
+
+    fn verifying_key(key: &Self::SigningKey) -> Self::VerifyingKey {
+        key.verifying_key()
+    }
+
+    fn encode_public_key(key: &Self::VerifyingKey) -> Vec<u8> {
+        key.to_bytes().to_vec()
+    }
+
+    fn decode_public_key(bytes: &[u8]) -> Option<Self::VerifyingKey> {
+        ed25519_dalek::VerifyingKey::from_bytes(bytes.try_into().ok()?).ok()
+    }
+
+    fn sign(key: &Self::SigningKey, message: &[u8]) -> Vec<u8> {
+        use ed25519_dalek::Signer;
+        key.sign(message).to_bytes().to_vec()
+    }
+
+    fn verify(key: &Self::VerifyingKey, message: &[u8], signature: &[u8]) -> bool {
+        ed25519_dalek::Signature::from_slice(signature)
+            .is_ok_and(|signature| key.verify_strict(message, &signature).is_ok())
+    }
+}
+
+/// A private key of any of the schemes.
+#[derive(Clone)]
+pub enum PrivateKey {
+    P256(<P256 as SignatureScheme>::SigningKey),
+    Secp256k1(<Secp256k1 as SignatureScheme>::SigningKey),
+    Ed25519(<Ed25519 as SignatureScheme>::SigningKey),
+}
+
+impl PrivateKey {
+    /// A random key of `scheme`.
+    pub fn generate(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::P256 => PrivateKey::P256(P256::generate()),
+            Scheme::Secp256k1 => PrivateKey::Secp256k1(Secp256k1::generate()),
+            Scheme::Ed25519 => PrivateKey::Ed25519(Ed25519::generate()),
+        }
+    }
+
+    /// The key of `scheme` with the bytes of `to_bytes`, `None` if they are not one.
+    pub fn from_bytes(scheme: Scheme, bytes: &[u8]) -> Option<Self> {
+        Some(match scheme {
+            Scheme::P256 => PrivateKey::P256(P256::signing_key_from_bytes(bytes)?),
+            Scheme::Secp256k1 => PrivateKey::Secp256k1(Secp256k1::signing_key_from_bytes(bytes)?),
+            Scheme::Ed25519 => PrivateKey::Ed25519(Ed25519::signing_key_from_bytes(bytes)?),
+        })
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        match self {
+            PrivateKey::P256(_) => P256::SCHEME,
+            PrivateKey::Secp256k1(_) => Secp256k1::SCHEME,
+            PrivateKey::Ed25519(_) => Ed25519::SCHEME,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            PrivateKey::P256(key) => P256::signing_key_to_bytes(key),
+            PrivateKey::Secp256k1(key) => Secp256k1::signing_key_to_bytes(key),
+            PrivateKey::Ed25519(key) => Ed25519::signing_key_to_bytes(key),
+        }
+    }
+
+    /// Encoded public key, the bytes an address hashes and a transaction carries.
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            PrivateKey::P256(key) => P256::encode_public_key(&P256::verifying_key(key)),
+            PrivateKey::Secp256k1(key) => {
+                Secp256k1::encode_public_key(&Secp256k1::verifying_key(key))
+            }
+            PrivateKey::Ed25519(key) => Ed25519::encode_public_key(&Ed25519::verifying_key(key)),
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            PrivateKey::P256(key) => P256::sign(key, message),
+            PrivateKey::Secp256k1(key) => Secp256k1::sign(key, message),
+            PrivateKey::Ed25519(key) => Ed25519::sign(key, message),
+        }
+    }
+}
+
+/// Whether `signature` signs `message` under the encoded `public_key` of `scheme`.
+pub fn verify(scheme: Scheme, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    fn verify_with<S: SignatureScheme>(
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        S::decode_public_key(public_key).is_some_and(|key| S::verify(&key, message, signature))
+    }
+    match scheme {
+        Scheme::P256 => verify_with::<P256>(public_key, message, signature),
+        Scheme::Secp256k1 => verify_with::<Secp256k1>(public_key, message, signature),
+        Scheme::Ed25519 => verify_with::<Ed25519>(public_key, message, signature),
+    }
+}
+
+/// Whether `public_key` is an encoded public key of `scheme`.
+pub fn is_public_key(scheme: Scheme, public_key: &[u8]) -> bool {
+    match scheme {
+        Scheme::P256 => P256::decode_public_key(public_key).is_some(),
+        Scheme::Secp256k1 => Secp256k1::decode_public_key(public_key).is_some(),
+        Scheme::Ed25519 => Ed25519::decode_public_key(public_key).is_some(),
+    }
+}
@@ -0,0 +1,42 @@
+use crate::core::wallet::WalletTransaction;
+
+/// Leads every payload so a transaction signature can never pass for a signature over
+/// anything else the same key signs.
+pub const DOMAIN: &[u8] = b"blockchain/transaction";
+/// Version of the layout below, bumped whenever it changes.
+pub const VERSION: u8 = 1;
+
+/// The bytes a transfer is signed over, the same for the wallet signing it and the chain
+/// checking it:
+///
+/// - the domain, its length in one byte first
+/// - the version, one byte
+/// - the chain id, four bytes
+/// - the tag of the scheme, one byte
+/// - the sender and the recipient, each as a four byte length and its UTF-8 bytes
+/// - the amount, the fee and the nonce, eight bytes each
+/// - the public key, as a four byte length and its bytes
+///
+/// Integers are big endian. `None` when the public key is not hex.
+pub fn signing_payload(transaction: &WalletTransaction, chain_id: u32) -> Option<Vec<u8>> {
+    let public_key = hex::decode(&transaction.public_key).ok()?;
+
+    let mut payload = Vec::new();
+    payload.push(DOMAIN.len() as u8);
+    payload.extend_from_slice(DOMAIN);
+    payload.push(VERSION);
+    payload.extend_from_slice(&chain_id.to_be_bytes());
+    payload.push(transaction.scheme.tag());
+    push_bytes(&mut payload, transaction.sender.as_bytes());
+    push_bytes(&mut payload, transaction.recipient.as_bytes());
+    payload.extend_from_slice(&transaction.amount.to_be_bytes());
+    payload.extend_from_slice(&transaction.fee.to_be_bytes());
+    payload.extend_from_slice(&transaction.nonce.to_be_bytes());
+    push_bytes(&mut payload, &public_key);
+    Some(payload)
+}
+
+fn push_bytes(payload: &mut Vec<u8>, bytes: &[u8]) {
+    payload.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    payload.extend_from_slice(bytes);
+}
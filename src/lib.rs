@@ -0,0 +1,4 @@
+pub mod apis;
+pub mod config;
+pub mod core;
+pub mod p2p;
@@ -1,10 +1,21 @@
-mod apis;
-use apis::server::Server;
-pub mod config;
-pub mod core;
+use blockchain::apis::server::Server;
+use blockchain::config::Config;
+use blockchain::core::wallet::Wallet;
+use blockchain::p2p::Node;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let config = Config::from_env();
+    let server = Server::new(config.clone(), &Wallet::with_network(config.network))?;
+
+    // Join the other nodes
+    Node::new(
+        server.blockchain(),
+        format!("127.0.0.1:{}", config.p2p_port),
+        config.peers.clone(),
+    )
+    .start()?;
+
     // Run the API server
-    Server::run().await
+    server.run().await
 }
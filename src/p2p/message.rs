@@ -0,0 +1,77 @@
+use crate::core::blockchain::block::Block;
+use crate::core::blockchain::Serialization;
+use crate::core::wallet::WalletTransaction;
+use serde::{Deserialize, Serialize};
+use std::io;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Longest message accepted from a peer.
+const MAX_MESSAGE_LEN: u64 = 32 * 1024 * 1024;
+
+/// Messages exchanged between nodes, one JSON document per line.
+/// Blocks and hashes travel as hex strings of their binary form.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Handshake, answered with the `Hello` of the other node.
+    Hello {
+        listen_addr: String,
+        height: usize,
+        #[serde(with = "decimal")]
+        work: u128,
+        peers: Vec<String>,
+    },
+    /// Asks for the blocks after the last hash of `locator` found in the main chain.
+    GetBlocks { locator: Vec<String> },
+    /// Answer to `GetBlocks`, `blocks` follow the block at `fork_height`.
+    Blocks {
+        fork_height: usize,
+        blocks: Vec<String>,
+    },
+    NewBlock { from: String, block: String },
+    NewTransaction { transaction: WalletTransaction },
+}
+
+/// Sends a u128 as a decimal string, tagged enums cannot carry it as a JSON number.
+mod decimal {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+pub fn encode_block(block: &Block) -> String {
+    hex::encode(block.serialization())
+}
+
+pub fn decode_block(block: &str) -> Option<Block> {
+    Block::try_deserialization(&hex::decode(block).ok()?)
+}
+
+/// Reads the next message, `None` when the peer closed the connection.
+pub async fn read_message<R: AsyncRead + Unpin>(reader: R) -> io::Result<Option<Message>> {
+    let mut line = String::new();
+    let mut reader = BufReader::new(tokio::io::AsyncReadExt::take(reader, MAX_MESSAGE_LEN));
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub async fn write_message(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+    stream.flush().await
+}
@@ -0,0 +1,4 @@
+pub mod message;
+pub mod node;
+
+pub use node::Node;
@@ -0,0 +1,322 @@
+use crate::core::blockchain::{AddBlockError, BlockChain, ChainEvent};
+use crate::core::wallet::WalletTransaction;
+use crate::p2p::message::{decode_block, encode_block, read_message, write_message, Message};
+use std::collections::HashSet;
+use std::io;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{self, timeout};
+
+/// How long a peer may take to connect or answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the node handshakes with every peer to catch up on missed blocks.
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+/// Most blocks sent in a single `Blocks` answer.
+const MAX_BLOCKS: usize = 500;
+/// Size of the seen set before it is cleared.
+const MAX_SEEN: usize = 10_000;
+/// Most peers kept, addresses learned past it are ignored until unreachable ones are
+/// dropped.
+pub const MAX_PEERS: usize = 100;
+
+/// Keeps a `BlockChain` in sync with other nodes over TCP.
+///
+/// Every connection carries one request and at most one answer. Transactions and blocks
+/// accepted by the chain are gossiped to all peers, and every `SYNC_INTERVAL` the node
+/// handshakes with its peers and downloads the blocks of any chain with more work.
+pub struct Node {
+    chain: Arc<RwLock<BlockChain>>,
+    listen_addr: String,
+    seeds: Vec<String>,
+    peers: Mutex<HashSet<String>>,
+    seen: Mutex<HashSet<Vec<u8>>>,
+}
+
+impl Node {
+    pub fn new(chain: Arc<RwLock<BlockChain>>, listen_addr: String, seeds: Vec<String>) -> Self {
+        let peers = seeds.iter().cloned().collect();
+        Node {
+            chain,
+            listen_addr,
+            seeds,
+            peers: Mutex::new(peers),
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Binds the listener and runs the node on its own thread and runtime, so mining
+    /// in the HTTP handlers never stalls the network.
+    pub fn start(self) -> io::Result<()> {
+        let listener = std::net::TcpListener::bind(&self.listen_addr)?;
+        listener.set_nonblocking(true)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        println!("p2p node listening on {}", self.listen_addr);
+
+        let node = Arc::new(self);
+        thread::Builder::new()
+            .name("p2p".to_string())
+            .spawn(move || {
+                runtime.block_on(async move {
+                    match TcpListener::from_std(listener) {
+                        Ok(listener) => node.run(listener).await,
+                        Err(e) => println!("p2p node failed to start: {}", e),
+                    }
+                })
+            })?;
+        Ok(())
+    }
+
+    async fn run(self: Arc<Self>, listener: TcpListener) {
+        tokio::spawn(self.clone().gossip());
+        tokio::spawn(self.clone().sync_periodically());
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let node = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = node.handle(stream).await {
+                            println!("p2p request failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => println!("p2p accept failed: {}", e),
+            }
+        }
+    }
+
+    async fn handle(self: Arc<Self>, mut stream: TcpStream) -> io::Result<()> {
+        let Some(message) = timeout(TIMEOUT, read_message(&mut stream)).await?? else {
+            return Ok(());
+        };
+
+        match message {
+            Message::Hello {
+                listen_addr,
+                work,
+                peers,
+                ..
+            } => {
+                // the node talking to us goes before the peers it knows of
+                self.add_peers([listen_addr.clone()].into_iter().chain(peers));
+                write_message(&mut stream, &self.hello()).await?;
+                if work > self.chain.read().unwrap().total_work() {
+                    tokio::spawn(self.clone().sync_with(listen_addr));
+                }
+            }
+            Message::GetBlocks { locator } => {
+                let locator: Vec<Vec<u8>> =
+                    locator.iter().filter_map(|h| hex::decode(h).ok()).collect();
+                let (fork_height, blocks) = self
+                    .chain
+                    .read()
+                    .unwrap()
+                    .blocks_after(&locator, MAX_BLOCKS);
+                let reply = Message::Blocks {
+                    fork_height,
+                    blocks: blocks.iter().map(encode_block).collect(),
+                };
+                write_message(&mut stream, &reply).await?;
+            }
+            Message::NewBlock { from, block } => {
+                // gossip has no answer, let the sender go before syncing
+                drop(stream);
+                self.receive_block(from, &block).await
+            }
+            Message::NewTransaction { transaction } => {
+                drop(stream);
+                self.receive_transaction(transaction)
+            }
+            Message::Blocks { .. } => println!("p2p received unexpected blocks"),
+        }
+
+        Ok(())
+    }
+
+    fn hello(&self) -> Message {
+        let chain = self.chain.read().unwrap();
+        Message::Hello {
+            listen_addr: self.listen_addr.clone(),
+            height: chain.len(),
+            work: chain.total_work(),
+            peers: self.peers.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+
+    fn add_peers<I: IntoIterator<Item = String>>(&self, addrs: I) {
+        let mut peers = self.peers.lock().unwrap();
+        for addr in addrs {
+            if peers.len() >= MAX_PEERS {
+                break;
+            }
+            if addr != self.listen_addr && peers.insert(addr.clone()) {
+                println!("p2p new peer {}", addr);
+            }
+        }
+    }
+
+    /// Records `id` as seen, returns false if it was already seen.
+    fn mark_seen(&self, id: Vec<u8>) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        if seen.len() >= MAX_SEEN {
+            seen.clear();
+        }
+        seen.insert(id)
+    }
+
+    async fn receive_block(self: Arc<Self>, from: String, block: &str) {
+        let Some(block) = decode_block(block) else {
+            println!("p2p received a malformed block from {}", from);
+            return;
+        };
+        if !self.mark_seen(block.hash()) {
+            return;
+        }
+
+        let result = self.chain.write().unwrap().add_block(block);
+        match result {
+            Ok(()) | Err(AddBlockError::AlreadyKnown) => {}
+            // we are missing its ancestors
+            Err(AddBlockError::UnknownParent) => self.sync_with(from).await,
+            Err(e) => println!("p2p rejected block from {}: {}", from, e),
+        }
+    }
+
+    fn receive_transaction(&self, tx: WalletTransaction) {
+        if !self.mark_seen(serde_json::to_vec(&tx).unwrap_or_default()) {
+            return;
+        }
+
+        if !self.chain.write().unwrap().add_transaction(&tx) {
+            println!("p2p rejected transaction from {}", tx.sender);
+        }
+    }
+
+    /// Forwards every transaction and block accepted by the chain to all peers.
+    async fn gossip(self: Arc<Self>) {
+        let mut events = self.chain.read().unwrap().subscribe();
+        loop {
+            let message = match events.recv().await {
+                Ok(ChainEvent::NewBlock(block)) => {
+                    self.mark_seen(block.hash());
+                    Message::NewBlock {
+                        from: self.listen_addr.clone(),
+                        block: encode_block(&block),
+                    }
+                }
+                Ok(ChainEvent::NewTransaction(transaction)) => {
+                    self.mark_seen(serde_json::to_vec(&transaction).unwrap_or_default());
+                    Message::NewTransaction { transaction }
+                }
+                Ok(ChainEvent::Reorg { .. }) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    println!("p2p gossip skipped {} events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            let peers: Vec<String> = self.peers.lock().unwrap().iter().cloned().collect();
+            for peer in peers {
+                let node = self.clone();
+                let message = message.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = node.request(&peer, &message).await {
+                        node.drop_peer(&peer, e);
+                    }
+                });
+            }
+        }
+    }
+
+    async fn sync_periodically(self: Arc<Self>) {
+        let mut interval = time::interval(SYNC_INTERVAL);
+        loop {
+            interval.tick().await;
+            let peers: Vec<String> = self.peers.lock().unwrap().iter().cloned().collect();
+            for peer in peers {
+                tokio::spawn(self.clone().handshake(peer));
+            }
+        }
+    }
+
+    async fn handshake(self: Arc<Self>, peer: String) {
+        let work = match self.request(&peer, &self.hello()).await {
+            Ok(Some(Message::Hello { work, peers, .. })) => {
+                self.add_peers(peers);
+                work
+            }
+            Ok(_) => return,
+            Err(e) => return self.drop_peer(&peer, e),
+        };
+
+        if work > self.chain.read().unwrap().total_work() {
+            self.sync_with(peer).await;
+        }
+    }
+
+    /// Downloads the blocks `peer` has after our last common block and adds them,
+    /// the chain reorganizes onto them once they carry more work.
+    async fn sync_with(self: Arc<Self>, peer: String) {
+        loop {
+            let locator = self.chain.read().unwrap().locator();
+            let request = Message::GetBlocks {
+                locator: locator.iter().map(hex::encode).collect(),
+            };
+            let blocks = match self.request(&peer, &request).await {
+                Ok(Some(Message::Blocks { blocks, .. })) => blocks,
+                Ok(_) => return,
+                Err(e) => return self.drop_peer(&peer, e),
+            };
+
+            let Some(blocks) = blocks
+                .iter()
+                .map(|b| decode_block(b))
+                .collect::<Option<Vec<_>>>()
+            else {
+                return println!("p2p received malformed blocks from {}", peer);
+            };
+            let count = blocks.len();
+            if count == 0 {
+                return;
+            }
+            for block in &blocks {
+                self.mark_seen(block.hash());
+            }
+
+            // blocks after an older common block build a side branch until it outweighs ours
+            let mut chain = self.chain.write().unwrap();
+            for block in blocks {
+                match chain.add_block(block) {
+                    Ok(()) | Err(AddBlockError::AlreadyKnown) => {}
+                    Err(e) => return println!("p2p rejected block from {}: {}", peer, e),
+                }
+            }
+            drop(chain);
+
+            if count < MAX_BLOCKS {
+                return;
+            }
+        }
+    }
+
+    /// Sends `message` to `peer` and waits for its answer, if any.
+    async fn request(&self, peer: &str, message: &Message) -> io::Result<Option<Message>> {
+        let mut stream = timeout(TIMEOUT, TcpStream::connect(peer)).await??;
+        write_message(&mut stream, message).await?;
+        timeout(TIMEOUT, read_message(&mut stream)).await?
+    }
+
+    /// Forgets a peer that could not be reached, seeds are kept to retry later.
+    fn drop_peer(&self, peer: &str, e: io::Error) {
+        println!("p2p peer {} is unreachable: {}", peer, e);
+        if !self.seeds.iter().any(|s| s == peer) {
+            self.peers.lock().unwrap().remove(peer);
+        }
+    }
+}
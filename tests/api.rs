@@ -0,0 +1,145 @@
+//! Transfers submitted over HTTP, signed by the node or by the wallet of the sender.
+
+use actix_web::{test, web, App};
+use blockchain::apis::handler::configure;
+use blockchain::config::Config;
+use blockchain::core::blockchain::BlockChain;
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::{Wallet, WalletTransaction};
+use serde_json::json;
+use std::sync::{Arc, RwLock};
+
+/// A chain whose first block pays 50 to `miner`.
+fn chain(miner: &Wallet) -> Arc<RwLock<BlockChain>> {
+    let config = Config {
+        difficulty: 1,
+        reward: 50,
+        mining_threads: 1,
+        ..Config::default()
+    };
+    let chain =
+        BlockChain::with_store(config, miner.get_address(), Box::new(MemoryStore::new())).unwrap();
+    Arc::new(RwLock::new(chain))
+}
+
+async fn post(
+    chain: &Arc<RwLock<BlockChain>>,
+    uri: &str,
+    body: serde_json::Value,
+) -> (u16, String) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(chain.clone()))
+            .configure(configure),
+    )
+    .await;
+    let request = test::TestRequest::post()
+        .uri(uri)
+        .set_json(body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    let status = response.status().as_u16();
+    let body = test::read_body(response).await;
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn private_key(wallet: &Wallet) -> String {
+    hex::encode(wallet.private_key().to_bytes())
+}
+
+fn signed_body(tx: &WalletTransaction) -> serde_json::Value {
+    json!({
+        "sender": tx.sender,
+        "recipient": tx.recipient,
+        "amount": tx.amount,
+        "fee": tx.fee,
+        "nonce": tx.nonce,
+        "scheme": tx.scheme.to_string(),
+        "publicKey": tx.public_key,
+        "signature": tx.signature,
+    })
+}
+
+#[actix_web::test]
+async fn transaction_signed_by_the_node_is_added() {
+    let miner = Wallet::new();
+    let chain = chain(&miner);
+    let recipient = Wallet::new().get_address();
+
+    let (status, _) = post(
+        &chain,
+        "/transaction",
+        json!({
+            "sender": miner.get_address(),
+            "recipient": recipient,
+            "amount": 10.0,
+            "fee": 1.0,
+            "privateKey": private_key(&miner),
+        }),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(chain.read().unwrap().mempool().len(), 1);
+}
+
+#[actix_web::test]
+async fn transaction_rejected_by_the_chain_is_a_bad_request() {
+    let miner = Wallet::new();
+    let chain = chain(&miner);
+    let recipient = Wallet::new().get_address();
+
+    // more than the sender owns, and a sender owning nothing
+    let poor = Wallet::new();
+    for (sender, amount) in [(&miner, 51.0), (&poor, 1.0)] {
+        let (status, body) = post(
+            &chain,
+            "/transaction",
+            json!({
+                "sender": sender.get_address(),
+                "recipient": recipient,
+                "amount": amount,
+                "privateKey": private_key(sender),
+            }),
+        )
+        .await;
+        assert_eq!(status, 400);
+        assert_eq!(body, "Transaction rejected by the blockchain");
+    }
+    assert!(chain.read().unwrap().mempool().is_empty());
+}
+
+#[actix_web::test]
+async fn transaction_signed_by_the_sender_is_added() {
+    let miner = Wallet::new();
+    let chain = chain(&miner);
+    let recipient = Wallet::new().get_address();
+
+    let tx = miner.sign_transaction(&recipient, 10, 1, 0);
+    let (status, _) = post(&chain, "/transaction/signed", signed_body(&tx)).await;
+    assert_eq!(status, 200);
+    assert_eq!(chain.read().unwrap().mempool().len(), 1);
+}
+
+#[actix_web::test]
+async fn transaction_signed_with_a_key_of_another_address_is_refused() {
+    let miner = Wallet::new();
+    let chain = chain(&miner);
+    let recipient = Wallet::new().get_address();
+
+    // the signature checks out against the key it carries, which is not the sender's
+    let thief = Wallet::new();
+    let forged = Wallet::new_from(thief.scheme(), &private_key(&thief), &miner.get_address())
+        .unwrap()
+        .sign_transaction(&recipient, 10, 1, 0);
+    let (status, body) = post(&chain, "/transaction/signed", signed_body(&forged)).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, "Public key does not belong to the sender");
+
+    // nor does claiming the key of the sender with the signature of another
+    let mut swapped = forged.clone();
+    swapped.public_key = miner.public_key_str();
+    let (status, body) = post(&chain, "/transaction/signed", signed_body(&swapped)).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, "Signature does not match the transaction");
+    assert!(chain.read().unwrap().mempool().is_empty());
+}
@@ -0,0 +1,132 @@
+//! Balances, nonces and transaction counts kept per height, checked against a rescan of
+//! the main chain before and after a reorganization.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::balance::AccountState;
+use blockchain::core::blockchain::transaction::Transaction;
+use blockchain::core::blockchain::utxo::UtxoSet;
+use blockchain::core::blockchain::BlockChain;
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::Wallet;
+
+fn config() -> Config {
+    Config {
+        difficulty: 1,
+        retarget_interval: 1_000_000,
+        reward: 50,
+        mining_threads: 1,
+        ..Config::default()
+    }
+}
+
+fn chain(miner: &Wallet) -> BlockChain {
+    BlockChain::with_store(config(), miner.get_address(), Box::new(MemoryStore::new())).unwrap()
+}
+
+fn mine(chain: &mut BlockChain, count: usize) {
+    for _ in 0..count {
+        assert!(chain.mining());
+    }
+}
+
+/// State of `address` after the block at `height`, from the blocks alone.
+fn rescan(chain: &BlockChain, address: &str, height: usize) -> AccountState {
+    let blocks: Vec<_> = (0..=height).map(|h| chain[h].clone()).collect();
+    let address = address.as_bytes();
+    let mut state = AccountState {
+        balance: UtxoSet::from_blocks(&blocks).balance(address),
+        ..AccountState::default()
+    };
+    for block in &blocks {
+        for tx in block
+            .transactions
+            .iter()
+            .filter_map(|t| Transaction::try_deserialization(t))
+        {
+            let sent = !tx.is_coinbase() && tx.sender_address == address;
+            if sent {
+                state.nonce += 1;
+            }
+            if sent || tx.outputs.iter().any(|o| o.address == address) {
+                state.tx_count += 1;
+            }
+        }
+    }
+    state
+}
+
+fn assert_matches_rescan(chain: &BlockChain, addresses: &[String]) {
+    for address in addresses {
+        for height in 0..chain.len() {
+            assert_eq!(
+                chain.account_at(address, height),
+                Some(rescan(chain, address, height)),
+                "{} at {}",
+                address,
+                height
+            );
+        }
+        assert_eq!(chain.account_at(address, chain.len()), None);
+        assert_eq!(
+            chain.account(address),
+            rescan(chain, address, chain.len() - 1)
+        );
+    }
+}
+
+#[test]
+fn index_matches_a_rescan_at_every_height() {
+    let miner = Wallet::new();
+    let recipient = Wallet::new().get_address();
+    let mut chain = chain(&miner);
+    mine(&mut chain, 2);
+
+    for nonce in 0..2 {
+        let tx = miner.sign_transaction(&recipient, 30, 2, nonce);
+        assert!(chain.add_transaction(&tx));
+        mine(&mut chain, 1);
+    }
+    assert_eq!(chain.account(&recipient).balance, 60);
+    assert_eq!(chain.account(&miner.get_address()).nonce, 2);
+
+    // past states stay as they were when their block was the tip
+    let at_two = chain.account_at(&miner.get_address(), 2).unwrap();
+    assert_eq!((at_two.balance, at_two.nonce), (100, 0));
+    assert_eq!(
+        chain.account_at(&recipient, 2),
+        Some(AccountState::default())
+    );
+
+    assert_matches_rescan(&chain, &[miner.get_address(), recipient]);
+}
+
+#[test]
+fn reorganization_rolls_the_index_back_to_the_fork() {
+    let miner = Wallet::new();
+    let recipient = Wallet::new().get_address();
+    let mut ours = chain(&miner);
+    mine(&mut ours, 1);
+    let tx = miner.sign_transaction(&recipient, 20, 1, 0);
+    assert!(ours.add_transaction(&tx));
+    mine(&mut ours, 1);
+    assert_eq!(ours.account(&recipient).balance, 20);
+
+    let other = Wallet::new();
+    let mut theirs = chain(&other);
+    mine(&mut theirs, 3);
+    for height in 1..theirs.len() {
+        ours.add_block(theirs[height].clone()).unwrap();
+    }
+    assert_eq!(ours.last_block().hash(), theirs.last_block().hash());
+
+    // nothing of the abandoned branch is left, past heights included
+    let addresses = [miner.get_address(), recipient.clone(), other.get_address()];
+    assert_eq!(ours.account(&recipient), AccountState::default());
+    assert_eq!(ours.account(&miner.get_address()), AccountState::default());
+    assert_eq!(ours.account(&other.get_address()).balance, 4 * 50);
+    assert_matches_rescan(&ours, &addresses);
+
+    // the index keeps following the new branch
+    mine(&mut ours, 1);
+    assert_matches_rescan(&ours, &addresses);
+}
@@ -0,0 +1,210 @@
+//! Filling blocks from the pool by fee rate within the limits of `Config`, and the limits
+//! and the reward `validate_block` holds every block to.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::block::Block;
+use blockchain::core::blockchain::difficulty::meets_target;
+use blockchain::core::blockchain::transaction::Transaction;
+use blockchain::core::blockchain::validation::InvalidReason;
+use blockchain::core::blockchain::{AddBlockError, BlockChain, Serialization};
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::Wallet;
+
+const REWARD: u64 = 50;
+
+fn config() -> Config {
+    Config {
+        difficulty: 1,
+        retarget_interval: 1_000_000,
+        reward: REWARD,
+        mining_threads: 1,
+        ..Config::default()
+    }
+}
+
+/// A chain where each of `senders` owns the coinbase of one block.
+fn chain(senders: &[&Wallet]) -> BlockChain {
+    let mut chain = BlockChain::with_store(
+        config(),
+        senders[0].get_address(),
+        Box::new(MemoryStore::new()),
+    )
+    .unwrap();
+    for sender in &senders[1..] {
+        chain.set_reward_address(sender.get_address());
+        assert!(chain.mining());
+    }
+    chain.set_reward_address(Wallet::new().get_address());
+    chain
+}
+
+/// Transactions of `block` after its coinbase.
+fn transfers(block: &Block) -> Vec<Transaction> {
+    block.transactions[1..]
+        .iter()
+        .map(|t| Transaction::try_deserialization(t).unwrap())
+        .collect()
+}
+
+fn coinbase_value(block: &Block) -> u64 {
+    Transaction::try_deserialization(&block.transactions[0])
+        .unwrap()
+        .output_value()
+        .unwrap()
+}
+
+/// Pending transactions as they go into a block.
+fn pending(chain: &BlockChain) -> Vec<Vec<u8>> {
+    chain.mempool().iter().map(|e| e.bytes.clone()).collect()
+}
+
+/// Adds the block on top of the tip holding a coinbase of `value` and `transactions`.
+fn add_block(
+    chain: &mut BlockChain,
+    value: u64,
+    transactions: Vec<Vec<u8>>,
+) -> Result<(), AddBlockError> {
+    let height = chain.len();
+    let target = chain.next_target();
+    let mut block = Block::new(0, chain.last_block().hash(), target);
+    block.transactions =
+        vec![Transaction::coinbase(height as u64, vec![0xaa; 34], value).serialization()];
+    block.transactions.extend(transactions);
+    block.update_merkle_root();
+    while !meets_target(&block.hash(), target) {
+        block.header.nonce += 1;
+    }
+    chain.add_block(block)
+}
+
+fn assert_invalid(result: Result<(), AddBlockError>, reason: InvalidReason) {
+    match result {
+        Err(AddBlockError::Invalid(e)) => assert_eq!(e.reason, reason),
+        other => panic!("expected {:?}, got {:?}", reason, other),
+    }
+}
+
+#[test]
+fn best_fee_rate_goes_first() {
+    let (a, b, c) = (Wallet::new(), Wallet::new(), Wallet::new());
+    let mut chain = chain(&[&a, &b, &c]);
+    let recipient = Wallet::new().get_address();
+    for (sender, fee) in [(&a, 1), (&b, 5), (&c, 3)] {
+        assert!(chain.add_transaction(&sender.sign_transaction(&recipient, 10, fee, 0)));
+    }
+
+    let template = chain.block_template();
+    let fees: Vec<u64> = transfers(&template).iter().map(|t| t.fee).collect();
+    assert_eq!(fees, vec![5, 3, 1]);
+    assert_eq!(coinbase_value(&template), REWARD + 9);
+
+    assert!(chain.mining());
+    assert!(chain.mempool().is_empty());
+    assert_eq!(coinbase_value(chain.last_block()), REWARD + 9);
+}
+
+#[test]
+fn child_paying_more_waits_for_its_parent() {
+    let (a, b) = (Wallet::new(), Wallet::new());
+    let mut chain = chain(&[&a, &b]);
+    let recipient = Wallet::new().get_address();
+
+    // the child spends the output of its pending parent, it owns nothing else
+    let child = Wallet::new();
+    assert!(chain.add_transaction(&a.sign_transaction(&child.get_address(), 20, 1, 0)));
+    assert!(chain.add_transaction(&child.sign_transaction(&recipient, 10, 9, 0)));
+    assert!(chain.add_transaction(&b.sign_transaction(&recipient, 10, 5, 0)));
+
+    let template = chain.block_template();
+    let order: Vec<(Vec<u8>, u64)> = transfers(&template)
+        .into_iter()
+        .map(|t| (t.sender_address, t.fee))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (b.get_address().into_bytes(), 5),
+            (a.get_address().into_bytes(), 1),
+            (child.get_address().into_bytes(), 9),
+        ]
+    );
+    assert!(chain.mining());
+    assert_eq!(chain.account(&recipient).balance, 20);
+}
+
+#[test]
+fn template_stops_at_the_block_limits() {
+    let (a, b, c) = (Wallet::new(), Wallet::new(), Wallet::new());
+    let mut chain = chain(&[&a, &b, &c]);
+    let recipient = Wallet::new().get_address();
+    for (sender, fee) in [(&a, 1), (&b, 5), (&c, 3)] {
+        assert!(chain.add_transaction(&sender.sign_transaction(&recipient, 10, fee, 0)));
+    }
+    let full = chain.block_template().serialization().len();
+
+    // room for the coinbase and two transactions
+    chain.config.max_block_transactions = 3;
+    let fees: Vec<u64> = transfers(&chain.block_template())
+        .iter()
+        .map(|t| t.fee)
+        .collect();
+    assert_eq!(fees, vec![5, 3]);
+
+    // one byte short of the lowest paying transaction
+    chain.config.max_block_transactions = 1_000;
+    chain.config.max_block_size = full - 1;
+    let template = chain.block_template();
+    let fees: Vec<u64> = transfers(&template).iter().map(|t| t.fee).collect();
+    assert_eq!(fees, vec![5, 3]);
+    assert!(template.serialization().len() < full);
+    assert_eq!(coinbase_value(&template), REWARD + 8);
+
+    // the best paying transactions are mined, the last waits for the next block
+    assert!(chain.mining());
+    assert_eq!(chain.mempool().len(), 1);
+    chain.config.max_block_size = full;
+    assert!(chain.mining());
+    assert!(chain.mempool().is_empty());
+}
+
+#[test]
+fn block_past_the_limits_is_refused() {
+    let (a, b) = (Wallet::new(), Wallet::new());
+    let mut chain = chain(&[&a, &b]);
+    let recipient = Wallet::new().get_address();
+    for sender in [&a, &b] {
+        assert!(chain.add_transaction(&sender.sign_transaction(&recipient, 10, 1, 0)));
+    }
+    let transactions = pending(&chain);
+
+    chain.config.max_block_transactions = 2;
+    assert_invalid(
+        add_block(&mut chain, REWARD, transactions.clone()),
+        InvalidReason::OversizedBlock,
+    );
+    chain.config.max_block_transactions = 3;
+    chain.config.max_block_size = 100;
+    assert_invalid(
+        add_block(&mut chain, REWARD, transactions.clone()),
+        InvalidReason::OversizedBlock,
+    );
+
+    chain.config.max_block_size = 1_000_000;
+    add_block(&mut chain, REWARD, transactions).unwrap();
+}
+
+#[test]
+fn coinbase_may_claim_the_subsidy_and_the_fees_but_no_more() {
+    let (a, b) = (Wallet::new(), Wallet::new());
+    let mut chain = chain(&[&a, &b]);
+    let recipient = Wallet::new().get_address();
+    assert!(chain.add_transaction(&a.sign_transaction(&recipient, 10, 4, 0)));
+    assert!(chain.add_transaction(&b.sign_transaction(&recipient, 10, 3, 0)));
+    let transactions = pending(&chain);
+
+    assert_invalid(
+        add_block(&mut chain, REWARD + 8, transactions.clone()),
+        InvalidReason::OversizedReward(REWARD + 8),
+    );
+    add_block(&mut chain, REWARD + 7, transactions).unwrap();
+}
@@ -0,0 +1,176 @@
+//! Compact targets, retargeting and how far ahead of the clock a block may be stamped.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::block::Block;
+use blockchain::core::blockchain::difficulty::{
+    compact, expand, from_difficulty, median_time, meets_target, next_target, work,
+};
+use blockchain::core::blockchain::validation::{InvalidReason, MAX_FUTURE_DRIFT};
+use blockchain::core::blockchain::{AddBlockError, BlockChain};
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::Wallet;
+
+const SECOND: u128 = 1_000_000_000;
+/// Target 0x10 followed by 27 zero bytes, doubled and halved exactly in compact form.
+const BITS: u32 = 0x1c10_0000;
+const LIMIT: u32 = 0x200f_ffff;
+
+fn config() -> Config {
+    Config {
+        target_block_time: 10,
+        retarget_interval: 10,
+        max_adjustment: 4,
+        ..Config::default()
+    }
+}
+
+/// Genesis followed by `len - 1` blocks at `bits`, one every `spacing` seconds.
+fn chain(len: usize, spacing: u128, bits: u32) -> Vec<Block> {
+    let mut blocks = vec![Block::genesis(bits)];
+    for height in 1..len {
+        let mut block = Block::new(0, blocks[height - 1].hash(), bits);
+        block.header.time_stamp = height as u128 * spacing * SECOND;
+        blocks.push(block);
+    }
+    blocks
+}
+
+fn retarget(blocks: &[Block]) -> u32 {
+    next_target(blocks.len(), |h| &blocks[h], &config())
+}
+
+#[test]
+fn compact_form_round_trips() {
+    for difficulty in 0..=64 {
+        let bits = from_difficulty(difficulty);
+        assert_eq!(compact(&expand(bits)), bits, "difficulty {}", difficulty);
+    }
+    for bits in [BITS, LIMIT, 0x0312_3456, 0x1d7f_ffff, 0x20ff_ffff] {
+        assert_eq!(compact(&expand(bits)), bits);
+    }
+}
+
+#[test]
+fn compact_form_places_the_mantissa_by_its_exponent() {
+    let target = expand(0x0312_3456);
+    assert_eq!(target[29..], [0x12, 0x34, 0x56]);
+    assert!(target[..29].iter().all(|b| *b == 0));
+
+    // bytes past the end of the number are dropped
+    let target = expand(0x0212_3456);
+    assert_eq!(target[30..], [0x12, 0x34]);
+    assert!(target[..30].iter().all(|b| *b == 0));
+
+    assert_eq!(expand(0), [0; 32]);
+    assert_eq!(compact(&[0; 32]), 0);
+}
+
+#[test]
+fn compact_form_has_no_sign_and_drops_what_overflows() {
+    // a set high bit in the mantissa is part of the number, not a sign
+    let target = expand(0x2080_0000);
+    assert_eq!(target[0], 0x80);
+    assert_eq!(compact(&target), 0x2080_0000);
+    assert!(work(0x2080_0000) < work(0x207f_ffff));
+
+    // an exponent past 32 bytes loses the leading mantissa bytes
+    let target = expand(0x2112_3456);
+    assert_eq!(target[..2], [0x34, 0x56]);
+    assert!(target[2..].iter().all(|b| *b == 0));
+
+    assert_eq!(compact(&[0xff; 32]), 0x20ff_ffff);
+}
+
+#[test]
+fn hashes_meet_targets_up_to_and_including_them() {
+    let target = expand(BITS);
+    assert!(meets_target(&target, BITS));
+
+    let mut above = target;
+    above[6] = 1;
+    assert!(!meets_target(&above, BITS));
+    assert!(meets_target(&[0; 32], BITS));
+    // only 32 byte hashes count
+    assert!(!meets_target(&[0; 31], BITS));
+}
+
+#[test]
+fn harder_targets_take_more_work() {
+    let mut previous = 0;
+    for difficulty in 1..=30 {
+        let work = work(from_difficulty(difficulty));
+        assert!(work > previous, "difficulty {}", difficulty);
+        previous = work;
+    }
+}
+
+#[test]
+fn target_is_kept_between_retargets() {
+    for len in [2, 5, 9, 11, 19] {
+        assert_eq!(retarget(&chain(len, 1, BITS)), BITS, "height {}", len);
+    }
+}
+
+#[test]
+fn target_follows_the_time_an_interval_took() {
+    // on time
+    assert_eq!(retarget(&chain(10, 10, BITS)), BITS);
+    assert_eq!(retarget(&chain(20, 10, BITS)), BITS);
+    // twice as slow gives twice the target, twice as fast half of it
+    assert_eq!(retarget(&chain(10, 20, BITS)), 0x1c20_0000);
+    assert_eq!(retarget(&chain(20, 5, BITS)), 0x1c08_0000);
+}
+
+#[test]
+fn retarget_is_clamped_by_max_adjustment() {
+    assert_eq!(retarget(&chain(10, 1_000, BITS)), 0x1c40_0000);
+    assert_eq!(retarget(&chain(10, 0, BITS)), 0x1c04_0000);
+}
+
+#[test]
+fn target_is_never_easier_than_the_limit() {
+    assert_eq!(retarget(&chain(10, 1_000, LIMIT)), LIMIT);
+    // four times the largest target does not fit in 256 bits
+    assert_eq!(retarget(&chain(10, 1_000, 0x20ff_ffff)), LIMIT);
+}
+
+#[test]
+fn time_stamp_pushed_ahead_does_not_stretch_the_interval() {
+    let mut blocks = chain(10, 10, BITS);
+    blocks[9].header.time_stamp += 3_600 * SECOND;
+    assert_eq!(retarget(&blocks), BITS);
+}
+
+#[test]
+fn median_time_leaves_out_genesis_and_skewed_blocks() {
+    let mut blocks = chain(30, 10, BITS);
+    assert_eq!(median_time(0, |h| &blocks[h]), (0, 0));
+    assert_eq!(median_time(1, |h| &blocks[h]), (1, 10 * SECOND));
+    assert_eq!(median_time(3, |h| &blocks[h]), (2, 20 * SECOND));
+    assert_eq!(median_time(29, |h| &blocks[h]), (24, 240 * SECOND));
+
+    blocks[29].header.time_stamp = u128::MAX;
+    assert_eq!(median_time(29, |h| &blocks[h]), (24, 240 * SECOND));
+}
+
+#[test]
+fn block_stamped_too_far_ahead_is_refused() {
+    let config = Config {
+        difficulty: 1,
+        ..Config::default()
+    };
+    let address = Wallet::new().get_address();
+    let mut chain = BlockChain::with_store(config, address, Box::new(MemoryStore::new())).unwrap();
+
+    let mut block = chain.block_template();
+    block.header.time_stamp += u128::from(MAX_FUTURE_DRIFT + 60) * SECOND;
+    while !meets_target(&block.hash(), block.header.target) {
+        block.header.nonce += 1;
+    }
+    match chain.add_block(block) {
+        Err(AddBlockError::Invalid(e)) => {
+            assert!(matches!(e.reason, InvalidReason::FutureTimeStamp))
+        }
+        other => panic!("expected an invalid block, got {:?}", other),
+    }
+}
@@ -0,0 +1,150 @@
+//! The subsidy schedule and how blocks are held to it by their coinbase.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::block::Block;
+use blockchain::core::blockchain::difficulty::{from_difficulty, meets_target};
+use blockchain::core::blockchain::emission::{subsidy, supply};
+use blockchain::core::blockchain::transaction::{OutPoint, Transaction, TxOutput};
+use blockchain::core::blockchain::utxo::UtxoSet;
+use blockchain::core::blockchain::validation::{validate_block, InvalidReason};
+use blockchain::core::blockchain::Serialization;
+
+fn schedule(reward: u64, halving_interval: usize, max_supply: u64) -> Config {
+    Config {
+        reward,
+        halving_interval,
+        max_supply,
+        ..Config::default()
+    }
+}
+
+/// The supply at every height up to `last` is the sum of the subsidies before it.
+fn assert_supply_adds_up(config: &Config, last: usize) {
+    let mut total: u64 = 0;
+    for height in 0..=last {
+        total += subsidy(height, config);
+        assert_eq!(supply(height, config), total, "height {}", height);
+    }
+}
+
+#[test]
+fn genesis_creates_nothing() {
+    let config = schedule(50, 10, u64::MAX);
+    assert_eq!(subsidy(0, &config), 0);
+    assert_eq!(supply(0, &config), 0);
+    assert_eq!(subsidy(1, &config), 50);
+}
+
+#[test]
+fn reward_halves_at_each_interval() {
+    let config = schedule(64, 10, u64::MAX);
+    assert_eq!(subsidy(9, &config), 64);
+    assert_eq!(subsidy(10, &config), 32);
+    assert_eq!(subsidy(19, &config), 32);
+    assert_eq!(subsidy(20, &config), 16);
+    assert_eq!(supply(9, &config), 9 * 64);
+    assert_eq!(supply(10, &config), 9 * 64 + 32);
+    assert_supply_adds_up(&config, 100);
+}
+
+#[test]
+fn reward_ends_after_64_halvings() {
+    let config = schedule(u64::MAX, 1, u64::MAX);
+    assert_eq!(subsidy(63, &config), 1);
+    assert_eq!(subsidy(64, &config), 0);
+    assert_eq!(subsidy(1_000, &config), 0);
+    assert_eq!(subsidy(usize::MAX, &config), 0);
+    assert_eq!(supply(usize::MAX, &config), supply(63, &config));
+    assert_supply_adds_up(&config, 70);
+}
+
+#[test]
+fn supply_never_passes_max_supply() {
+    let config = schedule(50, 1_000, 120);
+    assert_eq!(subsidy(2, &config), 50);
+    assert_eq!(subsidy(3, &config), 20);
+    assert_eq!(subsidy(4, &config), 0);
+    assert_eq!(supply(3, &config), 120);
+    assert_eq!(supply(100_000, &config), 120);
+    assert_supply_adds_up(&config, 50);
+}
+
+fn config() -> Config {
+    Config {
+        difficulty: 1,
+        reward: 50,
+        ..Config::default()
+    }
+}
+
+fn coinbase(height: u64, value: u64) -> Vec<u8> {
+    Transaction::coinbase(height, vec![0xaa; 34], value).serialization()
+}
+
+fn transfer() -> Vec<u8> {
+    let inputs = vec![OutPoint {
+        txid: vec![1; 32],
+        index: 0,
+    }];
+    let outputs = vec![TxOutput {
+        address: vec![0xbb; 34],
+        value: 10,
+    }];
+    Transaction::new(vec![0xaa; 34], inputs, outputs).serialization()
+}
+
+/// Checks the first block on top of genesis holding `transactions`.
+fn validate(transactions: Vec<Vec<u8>>) -> Result<(), InvalidReason> {
+    let config = config();
+    let target = from_difficulty(config.difficulty);
+    let genesis = Block::genesis(target);
+    let mut block = Block::new(0, genesis.hash(), target);
+    block.transactions = transactions;
+    block.update_merkle_root();
+    while !meets_target(&block.hash(), target) {
+        block.header.nonce += 1;
+    }
+
+    let mut utxos = UtxoSet::new();
+    utxos.apply_block(&genesis);
+    validate_block(1, &genesis, &block, target, &config, &utxos).map_err(|e| e.reason)
+}
+
+#[test]
+fn coinbase_may_claim_the_subsidy() {
+    assert_eq!(validate(vec![coinbase(1, 50)]), Ok(()));
+    assert_eq!(validate(vec![coinbase(1, 0)]), Ok(()));
+}
+
+#[test]
+fn coinbase_claiming_more_is_refused() {
+    assert_eq!(
+        validate(vec![coinbase(1, 51)]),
+        Err(InvalidReason::OversizedReward(51))
+    );
+}
+
+#[test]
+fn block_must_open_with_its_coinbase() {
+    assert_eq!(validate(vec![]), Err(InvalidReason::MissingCoinbase));
+    assert_eq!(
+        validate(vec![transfer(), coinbase(1, 50)]),
+        Err(InvalidReason::MissingCoinbase)
+    );
+    assert_eq!(
+        validate(vec![coinbase(1, 50), coinbase(1, 50)]),
+        Err(InvalidReason::MisplacedCoinbase(1))
+    );
+}
+
+#[test]
+fn coinbase_must_carry_the_height_of_its_block() {
+    assert_eq!(
+        validate(vec![coinbase(2, 50)]),
+        Err(InvalidReason::WrongCoinbaseHeight(2))
+    );
+    assert_eq!(
+        validate(vec![coinbase(0, 50)]),
+        Err(InvalidReason::WrongCoinbaseHeight(0))
+    );
+}
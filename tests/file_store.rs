@@ -0,0 +1,213 @@
+//! Crash recovery of the block file: whatever a crash leaves after the last complete
+//! record is dropped on open, and the offset index is rebuilt from the block file. A
+//! complete chain that fails validation is never dropped.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::block::Block;
+use blockchain::core::blockchain::{difficulty, BlockChain};
+use blockchain::core::storage::{ChainStore, FileStore};
+use blockchain::core::wallet::Wallet;
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+const BLOCKS_FILE: &str = "blocks.dat";
+const INDEX_FILE: &str = "blocks.idx";
+
+fn blocks(count: usize) -> Vec<Block> {
+    let target = difficulty::from_difficulty(1);
+    let mut blocks = vec![Block::genesis(target)];
+    for i in 1..count {
+        let mut block = Block::new(i as i32, blocks[i - 1].hash(), target);
+        block.transactions.push(vec![i as u8; 40]);
+        block.update_merkle_root();
+        blocks.push(block);
+    }
+    blocks
+}
+
+fn store_with(dir: &Path, blocks: &[Block]) -> FileStore {
+    let mut store = FileStore::open(dir).unwrap();
+    for block in blocks {
+        store.append_block(block).unwrap();
+    }
+    store
+}
+
+fn file_len(dir: &Path, name: &str) -> u64 {
+    fs::metadata(dir.join(name)).unwrap().len()
+}
+
+fn append_raw(dir: &Path, name: &str, bytes: &[u8]) {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(dir.join(name))
+        .unwrap();
+    file.write_all(bytes).unwrap();
+}
+
+#[test]
+fn blocks_survive_reopening() {
+    let dir = tempfile::tempdir().unwrap();
+    let blocks = blocks(3);
+    drop(store_with(dir.path(), &blocks));
+
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.load_blocks().unwrap(), blocks);
+}
+
+#[test]
+fn torn_trailing_record_is_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let blocks = blocks(4);
+    drop(store_with(dir.path(), &blocks[..3]));
+    let good_len = file_len(dir.path(), BLOCKS_FILE);
+
+    // a record announcing 100 bytes of which only 10 made it to disk
+    let mut torn = 100_u64.to_be_bytes().to_vec();
+    torn.extend([0xab; 10]);
+    append_raw(dir.path(), BLOCKS_FILE, &torn);
+
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(store.len(), 3);
+    assert_eq!(file_len(dir.path(), BLOCKS_FILE), good_len);
+    assert_eq!(store.load_blocks().unwrap(), blocks[..3]);
+
+    // the next block goes where the torn one was
+    store.append_block(&blocks[3]).unwrap();
+    drop(store);
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(store.load_blocks().unwrap(), blocks);
+}
+
+#[test]
+fn torn_length_prefix_is_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let blocks = blocks(2);
+    drop(store_with(dir.path(), &blocks));
+    let good_len = file_len(dir.path(), BLOCKS_FILE);
+
+    append_raw(dir.path(), BLOCKS_FILE, &[0, 0, 0]);
+
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(file_len(dir.path(), BLOCKS_FILE), good_len);
+    assert_eq!(store.load_blocks().unwrap(), blocks);
+}
+
+#[test]
+fn bad_checksum_truncates_back_to_the_last_good_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let blocks = blocks(3);
+    drop(store_with(dir.path(), &blocks[..2]));
+    let good_len = file_len(dir.path(), BLOCKS_FILE);
+    drop(store_with(dir.path(), &blocks[2..]));
+
+    // corrupt the last byte of the checksum of the last record
+    let path = dir.path().join(BLOCKS_FILE);
+    let mut bytes = fs::read(&path).unwrap();
+    *bytes.last_mut().unwrap() ^= 0xff;
+    fs::write(&path, bytes).unwrap();
+
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(store.len(), 2);
+    assert_eq!(file_len(dir.path(), BLOCKS_FILE), good_len);
+    assert_eq!(store.load_blocks().unwrap(), blocks[..2]);
+    assert_eq!(file_len(dir.path(), INDEX_FILE), 2 * 8);
+}
+
+#[test]
+fn index_is_rebuilt_when_it_disagrees_with_the_block_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let blocks = blocks(3);
+    drop(store_with(dir.path(), &blocks));
+    let index = fs::read(dir.path().join(INDEX_FILE)).unwrap();
+
+    // an index missing its last offset, as after a crash between the two writes
+    fs::write(dir.path().join(INDEX_FILE), &index[..index.len() - 8]).unwrap();
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(store.read_block(2).unwrap(), Some(blocks[2].clone()));
+    drop(store);
+    assert_eq!(fs::read(dir.path().join(INDEX_FILE)).unwrap(), index);
+
+    // an index pointing past the records that survived
+    append_raw(dir.path(), INDEX_FILE, &u64::MAX.to_be_bytes());
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.read_block(3).unwrap(), None);
+    drop(store);
+    assert_eq!(fs::read(dir.path().join(INDEX_FILE)).unwrap(), index);
+}
+
+#[test]
+fn chain_failing_validation_is_left_on_disk() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        difficulty: 1,
+        data_dir: dir.path().to_str().unwrap().to_string(),
+        ..Config::default()
+    };
+    let chain = BlockChain::new(config.clone(), Wallet::new().get_address()).unwrap();
+    assert_eq!(chain.len(), 2);
+    drop(chain);
+    let stored = fs::read(dir.path().join(BLOCKS_FILE)).unwrap();
+
+    // another difficulty gives another genesis block
+    let config = Config {
+        difficulty: 2,
+        ..config
+    };
+    let e = BlockChain::new(config, Wallet::new().get_address()).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(fs::read(dir.path().join(BLOCKS_FILE)).unwrap(), stored);
+}
+
+#[test]
+fn record_not_holding_a_block_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let blocks = blocks(2);
+    drop(store_with(dir.path(), &blocks));
+    let stored = fs::read(dir.path().join(BLOCKS_FILE)).unwrap();
+
+    // a whole record with a matching checksum, whose payload is not a block
+    let payload = [0xab_u8; 20];
+    let mut record = (payload.len() as u64).to_be_bytes().to_vec();
+    record.extend(payload);
+    record.extend(&Sha256::digest(payload)[..4]);
+    append_raw(dir.path(), BLOCKS_FILE, &record);
+    let with_record = fs::read(dir.path().join(BLOCKS_FILE)).unwrap();
+    assert_eq!(with_record.len(), stored.len() + record.len());
+
+    let mut store = FileStore::open(dir.path()).unwrap();
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.read_block(1).unwrap(), Some(blocks[1].clone()));
+    let e = store.read_block(2).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    drop(store);
+
+    let config = Config {
+        difficulty: 1,
+        data_dir: dir.path().to_str().unwrap().to_string(),
+        ..Config::default()
+    };
+    let e = BlockChain::new(config, Wallet::new().get_address()).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(fs::read(dir.path().join(BLOCKS_FILE)).unwrap(), with_record);
+}
+
+#[test]
+fn block_file_of_an_earlier_version_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    drop(store_with(dir.path(), &blocks(2)));
+
+    // every version up to 11 was written by a development build in another block format
+    let path = dir.path().join(BLOCKS_FILE);
+    let mut bytes = fs::read(&path).unwrap();
+    for version in 1..=11_u32 {
+        bytes[4..8].copy_from_slice(&version.to_be_bytes());
+        fs::write(&path, &bytes).unwrap();
+        let e = FileStore::open(dir.path()).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData, "{}", version);
+    }
+}
@@ -0,0 +1,128 @@
+//! Side branches and reorganizations between two nodes mining on the same genesis block.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::block::Block;
+use blockchain::core::blockchain::fork::MAX_FORK_DEPTH;
+use blockchain::core::blockchain::validation::InvalidReason;
+use blockchain::core::blockchain::{difficulty, AddBlockError, BlockChain};
+use blockchain::core::storage::{ChainStore, MemoryStore};
+use blockchain::core::wallet::Wallet;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+fn config() -> Config {
+    Config {
+        difficulty: 1,
+        // keep the target of the first blocks, they are mined far faster than intended
+        retarget_interval: 1_000_000,
+        mining_threads: 1,
+        ..Config::default()
+    }
+}
+
+/// A chain with the genesis block and `count` mined blocks on top.
+fn chain(store: Box<dyn ChainStore>, count: usize) -> BlockChain {
+    let mut chain = BlockChain::with_store(config(), Wallet::new().get_address(), store).unwrap();
+    while chain.len() < count + 1 {
+        assert!(chain.mining());
+    }
+    chain
+}
+
+fn blocks(chain: &BlockChain) -> Vec<Block> {
+    (0..chain.len()).map(|h| chain[h].clone()).collect()
+}
+
+/// Keeps its blocks where the test can see them and fails the next append on demand.
+#[derive(Debug, Default)]
+struct SharedStore {
+    blocks: Arc<Mutex<Vec<Block>>>,
+    fail_next_append: Arc<AtomicBool>,
+}
+
+impl ChainStore for SharedStore {
+    fn load_blocks(&mut self) -> io::Result<Vec<Block>> {
+        Ok(self.blocks.lock().unwrap().clone())
+    }
+
+    fn append_block(&mut self, block: &Block) -> io::Result<()> {
+        if self.fail_next_append.swap(false, Ordering::SeqCst) {
+            return Err(io::Error::other("disk full"));
+        }
+        self.blocks.lock().unwrap().push(block.clone());
+        Ok(())
+    }
+
+    fn truncate(&mut self, len: usize) -> io::Result<()> {
+        self.blocks.lock().unwrap().truncate(len);
+        Ok(())
+    }
+
+    fn load_pool(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        Ok(Vec::new())
+    }
+
+    fn save_pool(&mut self, _pool: &[Vec<u8>]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn reorganizes_onto_a_branch_with_more_work() {
+    let mut ours = chain(Box::new(MemoryStore::new()), 1);
+    let theirs = chain(Box::new(MemoryStore::new()), 3);
+
+    ours.add_block(theirs[1].clone()).unwrap();
+    assert_eq!(ours.last_block().hash(), ours[1].hash());
+    assert_ne!(ours[1].hash(), theirs[1].hash());
+
+    ours.add_block(theirs[2].clone()).unwrap();
+    ours.add_block(theirs[3].clone()).unwrap();
+    assert_eq!(blocks(&ours), blocks(&theirs));
+}
+
+#[test]
+fn side_block_must_carry_the_expected_target() {
+    let mut ours = chain(Box::new(MemoryStore::new()), 1);
+    let theirs = chain(Box::new(MemoryStore::new()), 1);
+
+    let mut easy = theirs[1].clone();
+    easy.header.target = difficulty::from_difficulty(0);
+    match ours.add_block(easy) {
+        Err(AddBlockError::Invalid(e)) => {
+            assert!(matches!(e.reason, InvalidReason::UnexpectedTarget(_)))
+        }
+        other => panic!("expected an invalid block, got {:?}", other),
+    }
+}
+
+#[test]
+fn side_block_forking_too_deep_is_refused() {
+    let mut ours = chain(Box::new(MemoryStore::new()), MAX_FORK_DEPTH + 1);
+    let theirs = chain(Box::new(MemoryStore::new()), 1);
+
+    assert!(matches!(
+        ours.add_block(theirs[1].clone()),
+        Err(AddBlockError::ForkTooDeep)
+    ));
+}
+
+#[test]
+fn failed_reorganization_leaves_the_store_as_it_was() {
+    let store = SharedStore::default();
+    let stored = store.blocks.clone();
+    let fail_next_append = store.fail_next_append.clone();
+    let mut ours = chain(Box::new(store), 2);
+    let theirs = chain(Box::new(MemoryStore::new()), 3);
+
+    ours.add_block(theirs[1].clone()).unwrap();
+    ours.add_block(theirs[2].clone()).unwrap();
+    fail_next_append.store(true, Ordering::SeqCst);
+    assert!(matches!(
+        ours.add_block(theirs[3].clone()),
+        Err(AddBlockError::Storage(_))
+    ));
+    assert_eq!(ours.len(), 3);
+    assert_eq!(*stored.lock().unwrap(), blocks(&ours));
+}
@@ -0,0 +1,189 @@
+//! Key derivation against the SLIP-0010 vectors for nist256p1, and from a BIP-39
+//! mnemonic to the address of a wallet.
+
+use blockchain::core::wallet::address::Network;
+use blockchain::core::wallet::hd::{DerivationPath, ExtendedKey, HdWallet};
+
+/// Path, chain code and private key of each step of a SLIP-0010 test chain.
+type Chain = [(&'static str, &'static str, &'static str)];
+
+const SEED_1: &str = "000102030405060708090a0b0c0d0e0f";
+const CHAIN_1: &Chain = &[
+    (
+        "m",
+        "beeb672fe4621673f722f38529c07392fecaa61015c80c34f29ce8b41b3cb6ea",
+        "612091aaa12e22dd2abef664f8a01a82cae99ad7441b7ef8110424915c268bc2",
+    ),
+    (
+        "m/0'",
+        "3460cea53e6a6bb5fb391eeef3237ffd8724bf0a40e94943c98b83825342ee11",
+        "6939694369114c67917a182c59ddb8cafc3004e63ca5d3b84403ba8613debc0c",
+    ),
+    (
+        "m/0'/1",
+        "4187afff1aafa8445010097fb99d23aee9f599450c7bd140b6826ac22ba21d0c",
+        "284e9d38d07d21e4e281b645089a94f4cf5a5a81369acf151a1c3a57f18b2129",
+    ),
+    (
+        "m/0'/1/2'",
+        "98c7514f562e64e74170cc3cf304ee1ce54d6b6da4f880f313e8204c2a185318",
+        "694596e8a54f252c960eb771a3c41e7e32496d03b954aeb90f61635b8e092aa7",
+    ),
+    (
+        "m/0'/1/2'/2",
+        "ba96f776a5c3907d7fd48bde5620ee374d4acfd540378476019eab70790c63a0",
+        "5996c37fd3dd2679039b23ed6f70b506c6b56b3cb5e424681fb0fa64caf82aaa",
+    ),
+    (
+        "m/0'/1/2'/2/1000000000",
+        "b9b7b82d326bb9cb5b5b121066feea4eb93d5241103c9e7a18aad40f1dde8059",
+        "21c4f269ef0a5fd1badf47eeacebeeaa3de22eb8e5b0adcd0f27dd99d34d0119",
+    ),
+];
+
+const SEED_2: &str = "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542";
+const CHAIN_2: &Chain = &[
+    (
+        "m",
+        "96cd4465a9644e31528eda3592aa35eb39a9527769ce1855beafc1b81055e75d",
+        "eaa31c2e46ca2962227cf21d73a7ef0ce8b31c756897521eb6c7b39796633357",
+    ),
+    (
+        "m/0",
+        "84e9c258bb8557a40e0d041115b376dd55eda99c0042ce29e81ebe4efed9b86a",
+        "d7d065f63a62624888500cdb4f88b6d59c2927fee9e6d0cdff9cad555884df6e",
+    ),
+    (
+        "m/0/2147483647'",
+        "f235b2bc5c04606ca9c30027a84f353acf4e4683edbd11f635d0dcc1cd106ea6",
+        "96d2ec9316746a75e7793684ed01e3d51194d81a42a3276858a5b7376d4b94b9",
+    ),
+    (
+        "m/0/2147483647'/1",
+        "7c0b833106235e452eba79d2bdd58d4086e663bc8cc55e9773d2b5eeda313f3b",
+        "974f9096ea6873a915910e82b29d7c338542ccde39d2064d1cc228f371542bbc",
+    ),
+    (
+        "m/0/2147483647'/1/2147483646'",
+        "5794e616eadaf33413aa309318a26ee0fd5163b70466de7a4512fd4b1a5c9e6a",
+        "da29649bbfaff095cd43819eda9a7be74236539a29094cd8336b07ed8d4eff63",
+    ),
+    (
+        "m/0/2147483647'/1/2147483646'/2",
+        "3bfb29ee8ac4484f09db09c2079b520ea5616df7820f071a20320366fbe226a7",
+        "bb0a77ba01cc31d77205d51d08bd313b979a71ef4de9b062f8958297e746bd67",
+    ),
+];
+
+/// The chain where a first candidate is past the order of the curve and derivation
+/// has to hash again.
+const CHAIN_RETRY: &Chain = &[
+    (
+        "m/28578'",
+        "e94c8ebe30c2250a14713212f6449b20f3329105ea15b652ca5bdfc68f6c65c2",
+        "06f0db126f023755d0b8d86d4591718a5210dd8d024e3e14b6159d63f53aa669",
+    ),
+    (
+        "m/28578'/33941",
+        "9e87fe95031f14736774cd82f25fd885065cb7c358c1edf813c72af535e83071",
+        "092154eed4af83e078ff9b84322015aefe5769e31270f62c3f66c33888335f3a",
+    ),
+];
+
+fn private_key(key: &ExtendedKey) -> String {
+    hex::encode(key.wallet(Network::Mainnet).private_key().to_bytes())
+}
+
+fn assert_chain(seed: &str, chain: &Chain) {
+    let master = ExtendedKey::master(&hex::decode(seed).unwrap());
+    for (path, chain_code, key) in chain {
+        let derived = master.derive(&path.parse().unwrap());
+        assert_eq!(hex::encode(derived.chain_code()), *chain_code, "{}", path);
+        assert_eq!(private_key(&derived), *key, "{}", path);
+    }
+}
+
+#[test]
+fn derivation_matches_slip10_vector_1() {
+    assert_chain(SEED_1, CHAIN_1);
+}
+
+#[test]
+fn derivation_matches_slip10_vector_2() {
+    assert_chain(SEED_2, CHAIN_2);
+}
+
+#[test]
+fn derivation_hashes_again_for_an_invalid_key() {
+    assert_chain(SEED_1, CHAIN_RETRY);
+}
+
+#[test]
+fn paths_read_as_they_print() {
+    for (path, _, _) in CHAIN_1.iter().chain(CHAIN_2) {
+        let parsed: DerivationPath = path.parse().unwrap();
+        assert_eq!(parsed.to_string(), *path);
+    }
+    assert_eq!(
+        "m/0h/1".parse::<DerivationPath>(),
+        "m/0'/1".parse::<DerivationPath>()
+    );
+    for invalid in ["", "0/1", "m/", "m/x", "m/2147483648", "m/-1"] {
+        assert!(invalid.parse::<DerivationPath>().is_err(), "{:?}", invalid);
+    }
+}
+
+/// The BIP-39 vector of the all `abandon` mnemonic with the passphrase `TREZOR`.
+const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                        abandon abandon abandon about";
+const PASSPHRASE: &str = "TREZOR";
+const SEED: &str = "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04";
+/// Private key at m/44'/0'/0'/0/0 of `SEED`.
+const FIRST_KEY: &str = "62c5b08cc0cee20013eae36a3e0d0a4312f0ab0c77c8a5dbc832abc121be999e";
+
+#[test]
+fn mnemonic_leads_to_the_addresses_of_its_seed() {
+    let from_mnemonic = HdWallet::from_mnemonic(MNEMONIC, PASSPHRASE, Network::Testnet).unwrap();
+    let from_seed = HdWallet::from_seed(&hex::decode(SEED).unwrap(), Network::Testnet);
+
+    let first = from_mnemonic.wallet(0);
+    assert_eq!(hex::encode(first.private_key().to_bytes()), FIRST_KEY);
+    for index in 0..5 {
+        let wallet = from_mnemonic.wallet(index);
+        assert_eq!(wallet.get_address(), from_seed.wallet(index).get_address());
+        assert_eq!(
+            wallet.get_address(),
+            from_mnemonic
+                .derive(&HdWallet::receive_path(index))
+                .get_address()
+        );
+    }
+
+    // the address is the one of the key, on the network asked for
+    let master = ExtendedKey::master(&hex::decode(SEED).unwrap());
+    let key = master.derive(&HdWallet::receive_path(0));
+    assert_eq!(
+        key.wallet(Network::Testnet).get_address(),
+        first.get_address()
+    );
+    assert!(first.get_address().starts_with('4'));
+
+    // another passphrase is another seed
+    let other = HdWallet::from_mnemonic(MNEMONIC, "", Network::Testnet).unwrap();
+    assert_ne!(other.wallet(0).get_address(), first.get_address());
+}
+
+#[test]
+fn invalid_mnemonics_are_refused() {
+    // a word outside the list, and a checksum that does not match
+    for mnemonic in [
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon xyzzy",
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon",
+    ] {
+        assert!(HdWallet::from_mnemonic(mnemonic, "", Network::Mainnet).is_err());
+    }
+    assert!(HdWallet::generate_mnemonic(11).is_err());
+    let generated = HdWallet::generate_mnemonic(24).unwrap().to_string();
+    assert_eq!(generated.split(' ').count(), 24);
+    assert!(HdWallet::from_mnemonic(&generated, "", Network::Mainnet).is_ok());
+}
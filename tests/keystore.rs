@@ -0,0 +1,118 @@
+//! Wallets encrypted on disk: opening them, refusing the wrong password or a file
+//! passed off as another, and staying inside the keystore directory.
+
+use blockchain::core::wallet::address::Network;
+use blockchain::core::wallet::keystore::{EncryptedKey, Keystore, KeystoreError};
+use blockchain::core::wallet::scheme::Scheme;
+use blockchain::core::wallet::Wallet;
+use std::fs;
+
+const PASSWORD: &str = "correct horse battery staple";
+
+#[test]
+fn wallet_survives_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let keystore = Keystore::open(dir.path()).unwrap();
+    for scheme in [Scheme::P256, Scheme::Secp256k1, Scheme::Ed25519] {
+        let wallet = Wallet::generate(scheme, Network::Testnet);
+        wallet.save(&keystore, PASSWORD).unwrap();
+
+        let loaded = Wallet::load(&keystore, &wallet.get_address(), PASSWORD).unwrap();
+        assert_eq!(loaded.get_address(), wallet.get_address());
+        assert_eq!(loaded.public_key_str(), wallet.public_key_str());
+        assert_eq!(loaded.scheme(), scheme);
+    }
+    assert_eq!(keystore.list().unwrap().len(), 3);
+}
+
+#[test]
+fn wrong_password_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let keystore = Keystore::open(dir.path()).unwrap();
+    let wallet = Wallet::new();
+    wallet.save(&keystore, PASSWORD).unwrap();
+
+    assert!(matches!(
+        Wallet::load(&keystore, &wallet.get_address(), "wrong"),
+        Err(KeystoreError::WrongPassword)
+    ));
+}
+
+#[test]
+fn key_moved_to_another_address_does_not_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let keystore = Keystore::open(dir.path()).unwrap();
+    let wallet = Wallet::new();
+    let other = Wallet::new().get_address();
+
+    // the address is authenticated with the key, so the file cannot be renamed
+    let mut key = EncryptedKey::encrypt(&wallet, PASSWORD);
+    key.address = other.clone();
+    keystore.write(&key).unwrap();
+    assert!(matches!(
+        Wallet::load(&keystore, &other, PASSWORD),
+        Err(KeystoreError::WrongPassword)
+    ));
+
+    // nor stored under a name other than its address
+    let key = EncryptedKey::encrypt(&wallet, PASSWORD);
+    fs::write(
+        dir.path().join(format!("{}.json", other)),
+        serde_json::to_vec(&key).unwrap(),
+    )
+    .unwrap();
+    assert!(matches!(
+        Wallet::load(&keystore, &other, PASSWORD),
+        Err(KeystoreError::Malformed(_))
+    ));
+}
+
+#[test]
+fn costlier_scrypt_parameters_are_refused() {
+    let wallet = Wallet::new();
+    let key = EncryptedKey::encrypt(&wallet, PASSWORD);
+    let changes: [fn(&mut EncryptedKey); 3] = [
+        |key| key.crypto.kdf_params.log_n = 40,
+        |key| key.crypto.kdf_params.r = 1 << 20,
+        |key| key.crypto.kdf_params.p = 1 << 20,
+    ];
+    for change in changes {
+        let mut costly = key.clone();
+        change(&mut costly);
+        assert!(matches!(
+            costly.decrypt(PASSWORD),
+            Err(KeystoreError::Malformed(_))
+        ));
+    }
+}
+
+#[test]
+fn paths_outside_the_keystore_are_not_reached() {
+    let root = tempfile::tempdir().unwrap();
+    let dir = root.path().join("keystore");
+    let keystore = Keystore::open(&dir).unwrap();
+    let wallet = Wallet::new();
+    let key = EncryptedKey::encrypt(&wallet, PASSWORD);
+    // a valid key file next to the keystore
+    fs::write(
+        root.path().join("outside.json"),
+        serde_json::to_vec(&key).unwrap(),
+    )
+    .unwrap();
+
+    for address in ["../outside", "..", "", "/etc/passwd", "a/b", "a.b"] {
+        assert!(
+            matches!(keystore.read(address), Err(KeystoreError::NotFound)),
+            "{:?}",
+            address
+        );
+
+        let mut moved = key.clone();
+        moved.address = address.to_string();
+        assert!(matches!(
+            keystore.write(&moved),
+            Err(KeystoreError::Malformed(_))
+        ));
+    }
+    assert!(keystore.list().unwrap().is_empty());
+}
@@ -0,0 +1,175 @@
+//! Admission, eviction and expiry of pending transactions.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::mempool::{Mempool, MempoolEntry, MempoolError};
+use blockchain::core::blockchain::transaction::{OutPoint, Transaction, TxOutput};
+
+fn config(max_transactions: usize) -> Config {
+    Config {
+        mempool_max_transactions: max_transactions,
+        mempool_max_size: usize::MAX,
+        mempool_expiry: 15,
+        ..Config::default()
+    }
+}
+
+fn outpoint(txid: &[u8], index: u32) -> OutPoint {
+    OutPoint {
+        txid: txid.to_vec(),
+        index,
+    }
+}
+
+/// Transfer of `sender` with `nonce` and `fee` spending `inputs`, all of the same size.
+fn entry(sender: u8, nonce: u64, fee: u64, inputs: Vec<OutPoint>) -> MempoolEntry {
+    let output = TxOutput {
+        address: vec![0xee; 34],
+        value: 10,
+    };
+    let mut tx = Transaction::new(vec![sender; 34], inputs, vec![output]);
+    tx.nonce = nonce;
+    tx.fee = fee;
+    MempoolEntry::new(tx, 0)
+}
+
+/// Entry of its own sender spending an output nobody else spends.
+fn single(sender: u8, fee: u64) -> MempoolEntry {
+    entry(sender, 0, fee, vec![outpoint(&[sender; 32], 0)])
+}
+
+fn txids(pool: &Mempool) -> Vec<Vec<u8>> {
+    pool.iter().map(|e| e.txid.clone()).collect()
+}
+
+#[test]
+fn insert_refuses_duplicates_and_conflicts() {
+    let mut pool = Mempool::new(&config(10));
+    let first = single(1, 5);
+    pool.insert(first.clone()).unwrap();
+
+    assert_eq!(pool.insert(first.clone()), Err(MempoolError::Duplicate));
+    // another sender spending the same output
+    let spender = entry(2, 0, 5, vec![outpoint(&[1; 32], 0)]);
+    assert_eq!(
+        pool.insert(spender),
+        Err(MempoolError::Conflict(first.txid.clone()))
+    );
+    // the same sender and nonce spending another output
+    let replacement = entry(1, 0, 9, vec![outpoint(&[9; 32], 0)]);
+    assert_eq!(
+        pool.insert(replacement),
+        Err(MempoolError::Conflict(first.txid.clone()))
+    );
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn insert_fails_when_full() {
+    let mut pool = Mempool::new(&config(2));
+    pool.insert(single(1, 1)).unwrap();
+    pool.insert(single(2, 1)).unwrap();
+    assert_eq!(pool.insert(single(3, 100)), Err(MempoolError::Full));
+
+    let size = single(1, 1).size();
+    let mut pool = Mempool::new(&Config {
+        mempool_max_size: 2 * size + size - 1,
+        ..config(10)
+    });
+    pool.insert(single(1, 1)).unwrap();
+    pool.insert(single(2, 1)).unwrap();
+    assert_eq!(pool.insert(single(3, 100)), Err(MempoolError::Full));
+    assert_eq!(pool.size(), 2 * size);
+}
+
+#[test]
+fn eviction_removes_the_lowest_rate_and_the_newest_among_equals() {
+    let mut pool = Mempool::new(&config(3));
+    let (high, old, new) = (single(1, 5), single(2, 1), single(3, 1));
+    for e in [&high, &old, &new] {
+        pool.insert(e.clone()).unwrap();
+    }
+
+    let incoming = single(4, 10);
+    let evicted = pool.evict_for(&incoming).unwrap();
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].txid, new.txid);
+    pool.insert(incoming.clone()).unwrap();
+    assert_eq!(txids(&pool), [high.txid, old.txid, incoming.txid]);
+}
+
+#[test]
+fn eviction_needs_a_higher_rate() {
+    let mut pool = Mempool::new(&config(2));
+    pool.insert(single(1, 5)).unwrap();
+    pool.insert(single(2, 7)).unwrap();
+    let before = txids(&pool);
+
+    assert_eq!(pool.evict_for(&single(3, 5)).unwrap_err(), MempoolError::Full);
+    assert_eq!(txids(&pool), before);
+
+    // it is enough that the transactions making room pay less
+    let mut pool = Mempool::new(&config(2));
+    pool.insert(single(1, 5)).unwrap();
+    pool.insert(single(2, 7)).unwrap();
+    assert_eq!(pool.evict_for(&single(3, 6)).unwrap().len(), 1);
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn eviction_spares_the_ancestors_of_the_incoming_transaction() {
+    // an earlier transaction of the same sender
+    let mut pool = Mempool::new(&config(2));
+    let parent = single(1, 1);
+    let other = single(2, 3);
+    pool.insert(parent.clone()).unwrap();
+    pool.insert(other.clone()).unwrap();
+
+    let child = entry(1, 1, 10, vec![outpoint(&[7; 32], 0)]);
+    let evicted = pool.evict_for(&child).unwrap();
+    assert_eq!(evicted[0].txid, other.txid);
+    pool.insert(child).unwrap();
+    assert!(pool.get(&parent.txid).is_some());
+
+    // a transaction of another sender whose output it spends, and that one's own
+    let mut pool = Mempool::new(&config(3));
+    let grandparent = single(1, 1);
+    let parent = entry(2, 0, 1, vec![outpoint(&grandparent.txid, 0)]);
+    let other = single(3, 3);
+    for e in [&grandparent, &parent, &other] {
+        pool.insert(e.clone()).unwrap();
+    }
+    let child = entry(4, 0, 10, vec![outpoint(&parent.txid, 0)]);
+    let evicted = pool.evict_for(&child).unwrap();
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].txid, other.txid);
+
+    // nothing to evict but ancestors
+    let mut pool = Mempool::new(&config(1));
+    pool.insert(single(1, 1)).unwrap();
+    let child = entry(1, 1, 10, vec![outpoint(&[7; 32], 0)]);
+    assert_eq!(pool.evict_for(&child).unwrap_err(), MempoolError::Full);
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn expiry_removes_what_was_received_too_long_ago() {
+    let mut pool = Mempool::new(&config(10));
+    let mut entries = Vec::new();
+    for (sender, received) in [(1, 0), (2, 10), (3, 20)] {
+        let mut e = single(sender, 1);
+        e.received = received;
+        pool.insert(e.clone()).unwrap();
+        entries.push(e);
+    }
+
+    assert!(pool.expire(14).is_empty());
+    let expired = pool.expire(25);
+    let expired: Vec<Vec<u8>> = expired.into_iter().map(|e| e.txid).collect();
+    assert_eq!(expired, [entries[0].txid.clone(), entries[1].txid.clone()]);
+    assert_eq!(txids(&pool), [entries[2].txid.clone()]);
+    assert_eq!(pool.size(), entries[2].size());
+
+    // what expired no longer holds its output or nonce
+    assert!(pool.by_sender(&[1; 34], 0).is_none());
+    pool.insert(entries[0].clone()).unwrap();
+}
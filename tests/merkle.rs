@@ -0,0 +1,112 @@
+//! Merkle roots and inclusion proofs over blocks of every small size.
+
+use blockchain::core::blockchain::merkle::{leaf_hash, prove, root, verify, MerkleProof};
+use sha2::{Digest, Sha256};
+
+fn transactions(count: usize) -> Vec<Vec<u8>> {
+    (0..count).map(|i| vec![i as u8; 10 + i]).collect()
+}
+
+fn node(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().to_vec()
+}
+
+#[test]
+fn empty_block_has_a_zero_root_and_no_proofs() {
+    assert_eq!(root(&[]), vec![0; 32]);
+    assert_eq!(prove(&[], 0), None);
+}
+
+#[test]
+fn single_transaction_is_its_own_root() {
+    let txs = transactions(1);
+    assert_eq!(root(&txs), leaf_hash(&txs[0]));
+
+    let proof = prove(&txs, 0).unwrap();
+    assert!(proof.siblings.is_empty());
+    assert!(verify(&txs[0], &proof, &root(&txs)));
+}
+
+#[test]
+fn odd_node_is_carried_up_unpaired() {
+    let txs = transactions(3);
+    let leaves: Vec<Vec<u8>> = txs.iter().map(|tx| leaf_hash(tx)).collect();
+    assert_eq!(root(&txs), node(&node(&leaves[0], &leaves[1]), &leaves[2]));
+
+    let txs = transactions(5);
+    let leaves: Vec<Vec<u8>> = txs.iter().map(|tx| leaf_hash(tx)).collect();
+    let left = node(&node(&leaves[0], &leaves[1]), &node(&leaves[2], &leaves[3]));
+    assert_eq!(root(&txs), node(&left, &leaves[4]));
+
+    // duplicating the last transaction does not give the same root
+    let mut doubled = transactions(3);
+    doubled.push(doubled[2].clone());
+    assert_ne!(root(&doubled), root(&transactions(3)));
+}
+
+#[test]
+fn every_position_proves_against_the_root() {
+    for count in 1..=17 {
+        let txs = transactions(count);
+        let root = root(&txs);
+        for (index, tx) in txs.iter().enumerate() {
+            let proof = prove(&txs, index).unwrap();
+            assert_eq!(proof.index, index);
+            assert_eq!(proof.leaf_count, count);
+            assert!(verify(tx, &proof, &root), "{} of {}", index, count);
+            // the proof belongs to its own transaction only
+            let other = &txs[(index + 1) % count];
+            assert_eq!(verify(other, &proof, &root), count == 1 && other == tx);
+        }
+        assert_eq!(prove(&txs, count), None);
+    }
+}
+
+#[test]
+fn swapped_siblings_are_rejected() {
+    let txs = transactions(4);
+    let root = root(&txs);
+    let mut proof = prove(&txs, 1).unwrap();
+    proof.siblings.swap(0, 1);
+    assert!(!verify(&txs[1], &proof, &root));
+
+    // a sibling put on the wrong side by claiming another position
+    let proof = prove(&txs, 1).unwrap();
+    let moved = MerkleProof { index: 0, ..proof };
+    assert!(!verify(&txs[1], &moved, &root));
+}
+
+#[test]
+fn proofs_of_the_wrong_shape_are_rejected() {
+    let txs = transactions(6);
+    let root = root(&txs);
+    let proof = prove(&txs, 4).unwrap();
+
+    let mut short = proof.clone();
+    short.siblings.pop();
+    assert!(!verify(&txs[4], &short, &root));
+
+    let mut long = proof.clone();
+    long.siblings.push(vec![0; 32]);
+    assert!(!verify(&txs[4], &long, &root));
+
+    let outside = MerkleProof {
+        index: 6,
+        ..proof.clone()
+    };
+    assert!(!verify(&txs[4], &outside, &root));
+
+    // an inner node does not pass for a transaction
+    let inner = node(&leaf_hash(&txs[0]), &leaf_hash(&txs[1]));
+    let upper = prove(&txs, 0).unwrap();
+    let lifted = MerkleProof {
+        index: 0,
+        leaf_count: 3,
+        siblings: upper.siblings[1..].to_vec(),
+    };
+    assert!(!verify(&inner, &lifted, &root));
+}
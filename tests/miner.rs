@@ -0,0 +1,68 @@
+//! Cancelling and stopping searches for a proof of work.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::BlockChain;
+use blockchain::core::miner::auto::{AutoMiner, MiningMode};
+use blockchain::core::miner::{Miner, MiningError};
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::Wallet;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+fn chain() -> Arc<RwLock<BlockChain>> {
+    let config = Config {
+        difficulty: 1,
+        mining_threads: 1,
+        ..Config::default()
+    };
+    let address = Wallet::new().get_address();
+    let chain = BlockChain::with_store(config, address, Box::new(MemoryStore::new())).unwrap();
+    Arc::new(RwLock::new(chain))
+}
+
+#[test]
+fn stopped_search_appends_nothing() {
+    let chain = chain();
+    let miner = Miner::new(chain.clone(), 1);
+    let len = chain.read().unwrap().len();
+
+    let stop = AtomicBool::new(true);
+    assert!(matches!(
+        miner.mine_until(&stop),
+        Err(MiningError::Cancelled)
+    ));
+    assert_eq!(chain.read().unwrap().len(), len);
+    assert!(!miner.is_mining());
+}
+
+#[test]
+fn cancel_while_idle_does_not_reach_the_next_search() {
+    let chain = chain();
+    let miner = Miner::new(chain.clone(), 1);
+    let len = chain.read().unwrap().len();
+
+    miner.cancel();
+    let mined = miner.mine().unwrap();
+    assert_eq!(mined.height, len);
+    assert_eq!(chain.read().unwrap().len(), len + 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn nothing_is_mined_once_auto_mining_stopped() {
+    let chain = chain();
+    let miner = Arc::new(Miner::new(chain.clone(), 1));
+    let auto_miner = AutoMiner::new(miner.clone(), chain.clone());
+
+    assert!(auto_miner.start(MiningMode::Continuous));
+    while auto_miner.blocks_mined() < 2 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(auto_miner.stop());
+    assert_eq!(auto_miner.mode(), None);
+
+    let len = chain.read().unwrap().len();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(chain.read().unwrap().len(), len);
+    assert!(!miner.is_mining());
+}
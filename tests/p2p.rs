@@ -0,0 +1,263 @@
+//! Nodes talking over localhost: the handshake, downloading blocks through a locator and
+//! gossip that does not bounce back.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::BlockChain;
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::Wallet;
+use blockchain::p2p::message::{decode_block, encode_block, read_message, write_message, Message};
+use blockchain::p2p::node::MAX_PEERS;
+use blockchain::p2p::Node;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout, Instant};
+
+fn config() -> Config {
+    Config {
+        difficulty: 1,
+        retarget_interval: 1_000_000,
+        mining_threads: 1,
+        ..Config::default()
+    }
+}
+
+/// A chain with the genesis block and `count` mined blocks on top.
+fn chain(count: usize) -> Arc<RwLock<BlockChain>> {
+    let mut chain = BlockChain::with_store(
+        config(),
+        Wallet::new().get_address(),
+        Box::new(MemoryStore::new()),
+    )
+    .unwrap();
+    while chain.len() < count + 1 {
+        assert!(chain.mining());
+    }
+    Arc::new(RwLock::new(chain))
+}
+
+/// An address nothing listens on yet.
+fn free_addr() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+fn start(chain: &Arc<RwLock<BlockChain>>, seeds: Vec<String>) -> String {
+    let addr = free_addr();
+    Node::new(chain.clone(), addr.clone(), seeds)
+        .start()
+        .unwrap();
+    addr
+}
+
+async fn request(addr: &str, message: &Message) -> Option<Message> {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    write_message(&mut stream, message).await.unwrap();
+    timeout(Duration::from_secs(5), read_message(&mut stream))
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn hello(listen_addr: &str, peers: Vec<String>) -> Message {
+    Message::Hello {
+        listen_addr: listen_addr.to_string(),
+        height: 1,
+        work: 0,
+        peers,
+    }
+}
+
+/// Peers known to the node at `addr`, as it tells in its `Hello`.
+async fn peers_of(addr: &str) -> Vec<String> {
+    match request(addr, &hello("127.0.0.1:1", Vec::new())).await {
+        Some(Message::Hello { peers, .. }) => peers,
+        other => panic!("expected a hello, got {:?}", other),
+    }
+}
+
+/// Waits until `done` holds, for at most 20 seconds.
+async fn wait_for(mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out");
+        sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// A peer that records every message sent to it, answering handshakes with no work.
+async fn listening_peer() -> (String, mpsc::UnboundedReceiver<Message>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let own_addr = addr.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let Ok(Some(message)) = read_message(&mut stream).await else {
+                continue;
+            };
+            if let Message::Hello { .. } = message {
+                let _ = write_message(&mut stream, &hello(&own_addr, Vec::new())).await;
+            }
+            let _ = sender.send(message);
+        }
+    });
+    (addr, receiver)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handshake_tells_the_chain_and_learns_peers() {
+    let chain = chain(2);
+    let addr = start(&chain, Vec::new());
+    sleep(Duration::from_millis(100)).await;
+
+    let reply = request(&addr, &hello("127.0.0.1:2", vec!["127.0.0.1:3".into()])).await;
+    let (len, work) = {
+        let chain = chain.read().unwrap();
+        (chain.len(), chain.total_work())
+    };
+    match reply {
+        Some(Message::Hello {
+            listen_addr,
+            height,
+            work: their_work,
+            peers,
+        }) => {
+            assert_eq!(listen_addr, addr);
+            assert_eq!(height, len);
+            assert_eq!(their_work, work);
+            assert!(peers.contains(&"127.0.0.1:2".to_string()));
+            assert!(peers.contains(&"127.0.0.1:3".to_string()));
+        }
+        other => panic!("expected a hello, got {:?}", other),
+    }
+
+    // a node never counts itself among its peers
+    request(&addr, &hello("127.0.0.1:2", vec![addr.clone()])).await;
+    assert!(!peers_of(&addr).await.contains(&addr));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn peers_learned_from_handshakes_are_capped() {
+    let chain = chain(0);
+    let addr = start(&chain, Vec::new());
+    sleep(Duration::from_millis(100)).await;
+
+    let many: Vec<String> = (0..10 * MAX_PEERS)
+        .map(|i| format!("10.0.{}.{}:6001", i / 256, i % 256))
+        .collect();
+    request(&addr, &hello("127.0.0.1:2", many)).await;
+    let peers = peers_of(&addr).await;
+    assert_eq!(peers.len(), MAX_PEERS);
+    // the node that introduced them goes before them
+    assert!(peers.contains(&"127.0.0.1:2".to_string()));
+
+    // once full, not even the node speaking is added
+    request(&addr, &hello("127.0.0.1:4", Vec::new())).await;
+    let peers = peers_of(&addr).await;
+    assert_eq!(peers.len(), MAX_PEERS);
+    assert!(!peers.contains(&"127.0.0.1:4".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn locator_finds_the_last_common_block() {
+    let chain = chain(4);
+    let addr = start(&chain, Vec::new());
+    sleep(Duration::from_millis(100)).await;
+    let blocks: Vec<_> = {
+        let chain = chain.read().unwrap();
+        (0..chain.len()).map(|h| chain[h].clone()).collect()
+    };
+
+    let get_blocks = |locator: Vec<Vec<u8>>| Message::GetBlocks {
+        locator: locator.iter().map(hex::encode).collect(),
+    };
+    let answer = |message| match message {
+        Some(Message::Blocks {
+            fork_height,
+            blocks,
+        }) => (
+            fork_height,
+            blocks
+                .iter()
+                .map(|b| decode_block(b).unwrap())
+                .collect::<Vec<_>>(),
+        ),
+        other => panic!("expected blocks, got {:?}", other),
+    };
+
+    // a locator whose first hashes are unknown, from a peer on another branch
+    let locator = vec![vec![7; 32], blocks[2].hash(), blocks[0].hash()];
+    let (fork_height, received) = answer(request(&addr, &get_blocks(locator)).await);
+    assert_eq!(fork_height, 2);
+    assert_eq!(received, blocks[3..]);
+
+    // a peer at the tip gets nothing, one with no known block gets all after genesis
+    let (fork_height, received) = answer(request(&addr, &get_blocks(vec![blocks[4].hash()])).await);
+    assert_eq!((fork_height, received.len()), (4, 0));
+    let (fork_height, received) = answer(request(&addr, &get_blocks(vec![vec![7; 32]])).await);
+    assert_eq!(fork_height, 0);
+    assert_eq!(received, blocks[1..]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn node_syncs_onto_the_chain_with_more_work() {
+    let ahead = chain(5);
+    let behind = chain(1);
+    let ahead_addr = start(&ahead, Vec::new());
+    sleep(Duration::from_millis(100)).await;
+
+    // the first periodic handshake finds more work at the seed and downloads it
+    let behind_addr = start(&behind, vec![ahead_addr.clone()]);
+    let tip = ahead.read().unwrap().last_block().hash();
+    wait_for(|| behind.read().unwrap().last_block().hash() == tip).await;
+    assert_eq!(behind.read().unwrap().len(), 6);
+
+    // the seed learned the node from its handshake and gossips its next block to it
+    assert!(peers_of(&ahead_addr).await.contains(&behind_addr));
+    assert!(ahead.write().unwrap().mining());
+    let tip = ahead.read().unwrap().last_block().hash();
+    wait_for(|| behind.read().unwrap().last_block().hash() == tip).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gossip_is_not_sent_back() {
+    let chain = chain(1);
+    let addr = start(&chain, Vec::new());
+    sleep(Duration::from_millis(100)).await;
+    let (peer, mut received) = listening_peer().await;
+    request(&addr, &hello(&peer, Vec::new())).await;
+
+    assert!(chain.write().unwrap().mining());
+    let block = chain.read().unwrap().last_block().clone();
+    let first = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(Message::NewBlock { block, .. }) = received.recv().await {
+                return block;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(decode_block(&first).unwrap(), block);
+
+    // the block coming back from the peer is neither added again nor forwarded
+    let len = chain.read().unwrap().len();
+    let mut stream = TcpStream::connect(&addr).await.unwrap();
+    let echo = Message::NewBlock {
+        from: peer.clone(),
+        block: encode_block(&block),
+    };
+    write_message(&mut stream, &echo).await.unwrap();
+    drop(stream);
+    sleep(Duration::from_millis(500)).await;
+    assert_eq!(chain.read().unwrap().len(), len);
+    while let Ok(message) = received.try_recv() {
+        assert!(
+            !matches!(message, Message::NewBlock { .. }),
+            "block gossiped twice"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Signing and verifying with each scheme, and signatures that must not verify.
+
+use blockchain::core::wallet::scheme::{verify, PrivateKey, Scheme};
+
+const SCHEMES: [Scheme; 3] = [Scheme::P256, Scheme::Secp256k1, Scheme::Ed25519];
+const MESSAGE: &[u8] = b"pay 50 to the recipient";
+
+/// Order of the group of P-256.
+const P256_ORDER: [u8; 32] =
+    hex_literal("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551");
+/// Order of the group of secp256k1.
+const SECP256K1_ORDER: [u8; 32] =
+    hex_literal("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+
+const fn hex_literal(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            _ => c - b'a' + 10,
+        }
+    }
+    let hex = hex.as_bytes();
+    let mut bytes = [0_u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        bytes[i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    bytes
+}
+
+/// `order - s`, big endian.
+fn negate(order: &[u8; 32], s: &[u8]) -> Vec<u8> {
+    let mut result = vec![0_u8; 32];
+    let mut borrow = 0_i16;
+    for i in (0..32).rev() {
+        let mut digit = order[i] as i16 - s[i] as i16 - borrow;
+        borrow = (digit < 0) as i16;
+        if digit < 0 {
+            digit += 256;
+        }
+        result[i] = digit as u8;
+    }
+    result
+}
+
+fn key(scheme: Scheme) -> PrivateKey {
+    PrivateKey::from_bytes(scheme, &[0x01; 32]).unwrap()
+}
+
+#[test]
+fn signatures_verify_under_their_own_key_and_message() {
+    for scheme in SCHEMES {
+        let key = key(scheme);
+        let signature = key.sign(MESSAGE);
+        assert_eq!(signature.len(), 64, "{}", scheme);
+        assert!(
+            verify(scheme, &key.public_key(), MESSAGE, &signature),
+            "{}",
+            scheme
+        );
+
+        assert!(!verify(scheme, &key.public_key(), b"pay 51", &signature));
+        let other = PrivateKey::from_bytes(scheme, &[0x02; 32]).unwrap();
+        assert!(!verify(scheme, &other.public_key(), MESSAGE, &signature));
+        let mut flipped = signature.clone();
+        flipped[10] ^= 1;
+        assert!(!verify(scheme, &key.public_key(), MESSAGE, &flipped));
+        assert!(!verify(
+            scheme,
+            &key.public_key(),
+            MESSAGE,
+            &signature[..63]
+        ));
+    }
+}
+
+#[test]
+fn signatures_of_one_scheme_fail_under_another() {
+    for signer in SCHEMES {
+        let key = key(signer);
+        let signature = key.sign(MESSAGE);
+        for verifier in SCHEMES.into_iter().filter(|s| *s != signer) {
+            assert!(
+                !verify(verifier, &key.public_key(), MESSAGE, &signature),
+                "{} signature under {}",
+                signer,
+                verifier
+            );
+            // nor under the key of the other scheme from the same secret
+            let twin = PrivateKey::from_bytes(verifier, &[0x01; 32]).unwrap();
+            assert!(!verify(verifier, &twin.public_key(), MESSAGE, &signature));
+        }
+    }
+}
+
+#[test]
+fn ecdsa_signatures_are_low_s_and_high_s_twins_fail() {
+    for (scheme, order) in [
+        (Scheme::P256, &P256_ORDER),
+        (Scheme::Secp256k1, &SECP256K1_ORDER),
+    ] {
+        let key = key(scheme);
+        let half = {
+            let mut half = [0_u8; 32];
+            let mut carry = 0;
+            for (i, byte) in order.iter().enumerate() {
+                half[i] = (carry << 7) | (byte >> 1);
+                carry = byte & 1;
+            }
+            half
+        };
+        for i in 0..32_u8 {
+            let message = [MESSAGE, &[i]].concat();
+            let signature = key.sign(&message);
+            let (r, s) = signature.split_at(32);
+            assert!(s <= &half[..], "{} signature {} has a high S", scheme, i);
+
+            // the same signature with S negated is valid ECDSA but must not verify
+            let twin = [r, &negate(order, s)].concat();
+            assert!(verify(scheme, &key.public_key(), &message, &signature));
+            assert!(
+                !verify(scheme, &key.public_key(), &message, &twin),
+                "{}",
+                scheme
+            );
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Lookups served from the indexes kept in step with the main chain: blocks by height,
+//! hash, nonce, time stamp and transaction, ranges of blocks, locators, transactions by
+//! txid, and how they all follow a reorganization.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::block::Block;
+use blockchain::core::blockchain::transaction::txid_of;
+use blockchain::core::blockchain::{merkle, BlockChain, BlockSearch, BlockSearchResult};
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::Wallet;
+use std::ops::Bound;
+
+fn config() -> Config {
+    Config {
+        difficulty: 1,
+        retarget_interval: 1_000_000,
+        reward: 50,
+        mining_threads: 1,
+        ..Config::default()
+    }
+}
+
+/// A chain mined by `miner` with the genesis block and `count` blocks on top.
+fn chain(miner: &Wallet, count: usize) -> BlockChain {
+    let mut chain =
+        BlockChain::with_store(config(), miner.get_address(), Box::new(MemoryStore::new()))
+            .unwrap();
+    while chain.len() < count + 1 {
+        assert!(chain.mining());
+    }
+    chain
+}
+
+/// Txids of the transactions of the main chain block at `height`.
+fn txids(chain: &BlockChain, height: usize) -> Vec<Vec<u8>> {
+    chain[height]
+        .transactions
+        .iter()
+        .map(|t| txid_of(t))
+        .collect()
+}
+
+/// Height of the block found, after checking it is the main chain block there.
+fn found(chain: &BlockChain, search: BlockSearch) -> Option<usize> {
+    match chain.search_block(search) {
+        BlockSearchResult::Success(height, block) => {
+            assert_eq!(block.hash(), chain[height].hash());
+            Some(height)
+        }
+        _ => None,
+    }
+}
+
+fn heights(chain: &BlockChain, blocks: (usize, &[Block])) -> Vec<usize> {
+    let (start, blocks) = blocks;
+    for (i, block) in blocks.iter().enumerate() {
+        assert_eq!(block.hash(), chain[start + i].hash());
+    }
+    (start..start + blocks.len()).collect()
+}
+
+#[test]
+fn blocks_are_found_by_height_and_hash() {
+    let chain = chain(&Wallet::new(), 4);
+    for height in 0..chain.len() {
+        assert_eq!(
+            found(&chain, BlockSearch::SearchByIndex(height)),
+            Some(height)
+        );
+        let hash = chain[height].hash();
+        assert_eq!(
+            found(&chain, BlockSearch::SearchByBlockHash(hash.clone())),
+            Some(height)
+        );
+        // the block whose previous hash it is comes next
+        let next = found(&chain, BlockSearch::SearchByPreviousHash(hash));
+        assert_eq!(next, Some(height + 1).filter(|h| *h < chain.len()));
+    }
+
+    assert!(matches!(
+        chain.search_block(BlockSearch::SearchByIndex(5)),
+        BlockSearchResult::FailOfIndex(5)
+    ));
+    assert!(matches!(
+        chain.search_block(BlockSearch::SearchByBlockHash(vec![7; 32])),
+        BlockSearchResult::FailOfBlockHash(h) if h == vec![7; 32]
+    ));
+    assert!(matches!(
+        chain.search_block(BlockSearch::SearchByPreviousHash(chain[4].hash())),
+        BlockSearchResult::FailOfPreviousHash(h) if h == chain[4].hash()
+    ));
+    // genesis has no parent in the chain
+    assert!(matches!(
+        chain.search_block(BlockSearch::SearchByPreviousHash(vec![0; 32])),
+        BlockSearchResult::FailOfPreviousHash(_)
+    ));
+}
+
+#[test]
+fn blocks_are_found_by_nonce_time_stamp_and_transaction() {
+    let chain = chain(&Wallet::new(), 4);
+    for height in 0..chain.len() {
+        // nonces repeat, the lowest block carrying one is found
+        let nonce = chain[height].header.nonce;
+        let lowest = (0..chain.len()).find(|h| chain[*h].header.nonce == nonce);
+        assert_eq!(found(&chain, BlockSearch::SearchByNonce(nonce)), lowest);
+
+        let time_stamp = chain[height].header.time_stamp;
+        assert_eq!(
+            found(&chain, BlockSearch::SearchByTimeStamp(time_stamp)),
+            Some(height)
+        );
+        // between two blocks
+        if chain.len() > height + 1 && chain[height + 1].header.time_stamp > time_stamp + 1 {
+            assert!(matches!(
+                chain.search_block(BlockSearch::SearchByTimeStamp(time_stamp + 1)),
+                BlockSearchResult::FailOfTimeStamp(t) if t == time_stamp + 1
+            ));
+        }
+
+        for transaction in &chain[height].transactions {
+            assert_eq!(
+                found(
+                    &chain,
+                    BlockSearch::SearchByTransaction(transaction.clone())
+                ),
+                Some(height)
+            );
+        }
+    }
+
+    assert!(matches!(
+        chain.search_block(BlockSearch::SearchByNonce(-1)),
+        BlockSearchResult::FailOfNonce(-1)
+    ));
+    let after_tip = chain.last_block().header.time_stamp + 1;
+    assert!(matches!(
+        chain.search_block(BlockSearch::SearchByTimeStamp(after_tip)),
+        BlockSearchResult::FailOfTimeStamp(_)
+    ));
+    assert!(matches!(
+        chain.search_block(BlockSearch::SearchByTransaction(vec![1, 2, 3])),
+        BlockSearchResult::FailOfTransaction(t) if t == vec![1, 2, 3]
+    ));
+}
+
+#[test]
+fn blocks_are_listed_by_height_and_time() {
+    let chain = chain(&Wallet::new(), 4);
+    assert_eq!(
+        heights(&chain, chain.blocks_by_height(..)),
+        vec![0, 1, 2, 3, 4]
+    );
+    assert_eq!(heights(&chain, chain.blocks_by_height(1..3)), vec![1, 2]);
+    assert_eq!(heights(&chain, chain.blocks_by_height(2..=3)), vec![2, 3]);
+    assert_eq!(
+        heights(
+            &chain,
+            chain.blocks_by_height((Bound::Excluded(1), Bound::Included(2)))
+        ),
+        vec![2]
+    );
+    assert_eq!(heights(&chain, chain.blocks_by_height(3..)), vec![3, 4]);
+    assert_eq!(chain.blocks_by_height(3..100).1.len(), 2);
+    assert_eq!(chain.blocks_by_height(5..).1.len(), 0);
+    assert_eq!(chain.blocks_by_height(100..).0, 5);
+    assert_eq!(
+        chain
+            .blocks_by_height((Bound::Included(3), Bound::Excluded(1)))
+            .1
+            .len(),
+        0
+    );
+
+    let time = |h: usize| chain[h].header.time_stamp;
+    assert_eq!(
+        heights(&chain, chain.blocks_by_time(..)),
+        vec![0, 1, 2, 3, 4]
+    );
+    assert_eq!(
+        heights(&chain, chain.blocks_by_time(time(1)..time(3))),
+        vec![1, 2]
+    );
+    assert_eq!(
+        heights(&chain, chain.blocks_by_time(time(1)..=time(3))),
+        vec![1, 2, 3]
+    );
+    assert_eq!(
+        heights(
+            &chain,
+            chain.blocks_by_time((Bound::Excluded(time(1)), Bound::Unbounded))
+        ),
+        vec![2, 3, 4]
+    );
+    assert_eq!(
+        heights(&chain, chain.blocks_by_time(time(4) + 1..)),
+        Vec::<usize>::new()
+    );
+    assert_eq!(chain.blocks_by_time(time(4) + 1..).0, 5);
+    assert_eq!(chain.blocks_by_time(time(3)..time(1)).1.len(), 0);
+}
+
+#[test]
+fn locator_leads_to_the_blocks_after_the_last_common_one() {
+    let chain = chain(&Wallet::new(), 15);
+    let locator = chain.locator();
+    // dense for the last ten blocks, then doubling the step, down to genesis
+    let located: Vec<usize> = locator
+        .iter()
+        .map(|h| found(&chain, BlockSearch::SearchByBlockHash(h.clone())).unwrap())
+        .collect();
+    assert_eq!(located, vec![15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 4, 0]);
+
+    let (fork_height, blocks) = chain.blocks_after(&[vec![7; 32], chain[9].hash()], 3);
+    assert_eq!(fork_height, 9);
+    assert_eq!(
+        blocks,
+        vec![chain[10].clone(), chain[11].clone(), chain[12].clone()]
+    );
+    assert_eq!(chain.blocks_after(&locator, 500), (15, Vec::new()));
+    let (fork_height, blocks) = chain.blocks_after(&[vec![7; 32]], 500);
+    assert_eq!((fork_height, blocks.len()), (0, 15));
+}
+
+#[test]
+fn block_lookups_follow_a_reorganization() {
+    let mut ours = chain(&Wallet::new(), 2);
+    let theirs = chain(&Wallet::new(), 3);
+    let abandoned: Vec<_> = (1..ours.len()).map(|h| ours[h].clone()).collect();
+    for height in 1..theirs.len() {
+        ours.add_block(theirs[height].clone()).unwrap();
+    }
+    assert_eq!(ours.last_block().hash(), theirs.last_block().hash());
+
+    for block in &abandoned {
+        assert_eq!(
+            found(&ours, BlockSearch::SearchByBlockHash(block.hash())),
+            None
+        );
+        assert_eq!(
+            found(&ours, BlockSearch::SearchByPreviousHash(block.hash())),
+            None
+        );
+        for transaction in &block.transactions {
+            assert_eq!(
+                found(&ours, BlockSearch::SearchByTransaction(transaction.clone())),
+                None
+            );
+        }
+    }
+    for height in 0..ours.len() {
+        assert_eq!(
+            found(&ours, BlockSearch::SearchByBlockHash(theirs[height].hash())),
+            Some(height)
+        );
+        let nonce = theirs[height].header.nonce;
+        let lowest = (0..theirs.len()).find(|h| theirs[*h].header.nonce == nonce);
+        assert_eq!(found(&ours, BlockSearch::SearchByNonce(nonce)), lowest);
+        assert_eq!(
+            found(
+                &ours,
+                BlockSearch::SearchByTimeStamp(theirs[height].header.time_stamp)
+            ),
+            Some(height)
+        );
+    }
+    // the fork point now leads to their block
+    assert_eq!(
+        found(&ours, BlockSearch::SearchByPreviousHash(ours[0].hash())),
+        Some(1)
+    );
+    assert_eq!(ours[1].hash(), theirs[1].hash());
+
+    // a peer still on our old branch gets the blocks after genesis
+    let locator: Vec<_> = abandoned
+        .iter()
+        .rev()
+        .map(|b| b.hash())
+        .chain([ours[0].hash()])
+        .collect();
+    let (fork_height, blocks) = ours.blocks_after(&locator, 500);
+    assert_eq!(fork_height, 0);
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[2].hash(), theirs[3].hash());
+}
+
+#[test]
+fn transactions_are_found_by_txid_with_their_confirmations() {
+    let miner = Wallet::new();
+    let mut chain = chain(&miner, 1);
+    let recipient = Wallet::new().get_address();
+    let tx = miner.sign_transaction(&recipient, 10, 1, 0);
+    assert!(chain.add_transaction(&tx));
+
+    // pending, it has no location yet
+    let txid = chain.mempool().iter().next().unwrap().txid.clone();
+    let pending = chain.find_transaction(&txid).unwrap();
+    assert_eq!(pending.location, None);
+    assert_eq!(pending.confirmations, 0);
+    assert!(chain.prove_transaction(&txid).is_none());
+
+    assert!(chain.mining());
+    assert_eq!(txids(&chain, 2)[1], txid);
+    let found = chain.find_transaction(&txid).unwrap();
+    assert_eq!(found.location, Some((2, 1)));
+    assert_eq!(found.confirmations, 1);
+    assert_eq!(found.transaction.txid(), txid);
+
+    // every block on top is one more confirmation
+    assert!(chain.mining());
+    assert_eq!(chain.find_transaction(&txid).unwrap().confirmations, 2);
+    let coinbase = chain.find_transaction(&txids(&chain, 1)[0]).unwrap();
+    assert_eq!(coinbase.location, Some((1, 0)));
+    assert_eq!(coinbase.confirmations, 3);
+    assert!(coinbase.transaction.is_coinbase());
+
+    let (height, proof) = chain.prove_transaction(&txid).unwrap();
+    assert_eq!(height, 2);
+    assert!(merkle::verify(
+        &chain[2].transactions[1],
+        &proof,
+        &chain[2].header.merkle_root
+    ));
+
+    let records = chain.block_transactions(2);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].location, Some((2, 1)));
+    assert!(chain.block_transactions(chain.len()).is_empty());
+    // a coinbase in every block after genesis and the transfer
+    assert_eq!(chain.transaction_count(), 3 + 1);
+    assert!(chain.find_transaction(&[0; 32]).is_none());
+}
+
+#[test]
+fn transactions_of_an_abandoned_branch_are_no_longer_found() {
+    let miner = Wallet::new();
+    let mut ours = chain(&miner, 1);
+    let recipient = Wallet::new().get_address();
+    assert!(ours.add_transaction(&miner.sign_transaction(&recipient, 10, 1, 0)));
+    assert!(ours.mining());
+    let abandoned: Vec<Vec<u8>> = (1..ours.len()).flat_map(|h| txids(&ours, h)).collect();
+    assert_eq!(abandoned.len(), 3);
+
+    let theirs = chain(&Wallet::new(), 3);
+    for height in 1..theirs.len() {
+        ours.add_block(theirs[height].clone()).unwrap();
+    }
+    assert_eq!(ours.last_block().hash(), theirs.last_block().hash());
+
+    // the transfer lost its funds with the branch, nothing of it is left
+    for txid in &abandoned {
+        assert!(ours.find_transaction(txid).is_none());
+        assert!(ours.prove_transaction(txid).is_none());
+    }
+    for height in 1..ours.len() {
+        let txid = &txids(&ours, height)[0];
+        let found = ours.find_transaction(txid).unwrap();
+        assert_eq!(found.location, Some((height, 0)));
+        assert_eq!(found.confirmations, ours.len() - height);
+    }
+    assert_eq!(ours.transaction_count(), 3);
+}
@@ -0,0 +1,171 @@
+//! Golden vectors of the signing payload. Each transfer of 50 with a fee of 1 and nonce 3
+//! is signed on mainnet by the key with 32 bytes of 0x01 to the address of the key with 32
+//! bytes of 0x02. ECDSA signatures are deterministic (RFC 6979) and so is Ed25519, so the
+//! signatures are fixed too. Wallets outside this crate can check their encoding and
+//! signing against them.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::BlockChain;
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::address::Network;
+use blockchain::core::wallet::scheme::{PrivateKey, Scheme};
+use blockchain::core::wallet::signing::signing_payload;
+use blockchain::core::wallet::{Wallet, WalletTransaction};
+
+const AMOUNT: u64 = 50;
+const FEE: u64 = 1;
+const NONCE: u64 = 3;
+const MAINNET_CHAIN_ID: u32 = 1;
+
+struct Vector {
+    scheme: Scheme,
+    sender: &'static str,
+    recipient: &'static str,
+    public_key: &'static str,
+    payload: &'static str,
+    signature: &'static str,
+}
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        scheme: Scheme::P256,
+        sender: "113dVv4Gy9MiirTrfVDvS6DSmvyWCSbVTTJ",
+        recipient: "118PzsQWoZibExyWW9QurBW9ZJ9oRxArmxv",
+        public_key: "6ff03b949241ce1dadd43519e6960e0a85b41a69a05c328103aa2bce1594ca163c4f753a55bf01dc53f6c0b0c7eee78b40c6ff7d25a96e2282b989cef71c144a",
+        payload: concat!(
+            "16626c6f636b636861696e2f7472616e73616374696f6e0100000001000000002331313364567634",
+            "4779394d696972547266564476533644536d7679574353625654544a00000023313138507a735157",
+            "6f5a69624578795757395175724257395a4a396f527841726d787600000000000000320000000000",
+            "0000010000000000000003000000406ff03b949241ce1dadd43519e6960e0a85b41a69a05c328103",
+            "aa2bce1594ca163c4f753a55bf01dc53f6c0b0c7eee78b40c6ff7d25a96e2282b989cef71c144a",
+        ),
+        signature: concat!(
+            "762ca35358e3b007e5869a969f17c0a668b1abea65f5f19a9577fb57ffe58c89053e8c221d19014b",
+            "f020396e798a69db5e4ff7949f7f2481fcf1ffb5f9aa108f",
+        ),
+    },
+    Vector {
+        scheme: Scheme::Secp256k1,
+        sender: "1bwz6PQcJWheuiGF65ikk3a52kpWRVZwX7",
+        recipient: "1hN2UXEYBLEH1dLop2c3qwUBfEfhDm5czC",
+        public_key: "1b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f70beaf8f588b541507fed6a642c5ab42dfdf8120a7f639de5122d47a69a8e8d1",
+        payload: concat!(
+            "16626c6f636b636861696e2f7472616e73616374696f6e010000000101000000223162777a365051",
+            "634a576865756947463635696b6b336135326b705752565a7758370000002231684e325558455942",
+            "4c454831644c6f703263337177554266456668446d35637a43000000000000003200000000000000",
+            "010000000000000003000000401b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5",
+            "e9d5dd078f70beaf8f588b541507fed6a642c5ab42dfdf8120a7f639de5122d47a69a8e8d1",
+        ),
+        signature: concat!(
+            "991e4c0e0a61bafabeea4b6dcfdaee5e4b21ab29c59b018019f93eb87c8ec9720ec978297aed1dd5",
+            "16400cf7a672b07efc641b39520a02684b6cf43d6c0f4614",
+        ),
+    },
+    Vector {
+        scheme: Scheme::Ed25519,
+        sender: "12AS4WMXAGMRkbgq2fRT7vK8RFSuDtL7yno",
+        recipient: "1ouECmHwAUCvvXQf4EuJfivkXpX9GKd2HP",
+        public_key: "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c",
+        payload: concat!(
+            "16626c6f636b636861696e2f7472616e73616374696f6e010000000102000000233132415334574d",
+            "5841474d526b6267713266525437764b385246537544744c37796e6f00000022316f7545436d4877",
+            "41554376765851663445754a6669766b58705839474b643248500000000000000032000000000000",
+            "00010000000000000003000000208a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf374",
+            "8801b40f6f5c",
+        ),
+        signature: concat!(
+            "ba8c5970bb71628186ebbb3adfaf32049ee9a47725a16aa32813461236497f8755fba894a71e6923",
+            "187234477afc961a338652ac9b04f29d496b52207135690f",
+        ),
+    },
+];
+
+fn transaction(vector: &Vector) -> WalletTransaction {
+    WalletTransaction {
+        sender: vector.sender.to_string(),
+        recipient: vector.recipient.to_string(),
+        amount: AMOUNT,
+        fee: FEE,
+        nonce: NONCE,
+        scheme: vector.scheme,
+        public_key: vector.public_key.to_string(),
+        signature: vector.signature.to_string(),
+    }
+}
+
+#[test]
+fn payload_matches_vectors() {
+    for vector in VECTORS {
+        let payload = signing_payload(&transaction(vector), MAINNET_CHAIN_ID).unwrap();
+        assert_eq!(hex::encode(payload), vector.payload, "{}", vector.scheme);
+    }
+}
+
+#[test]
+fn wallets_sign_vectors() {
+    for vector in VECTORS {
+        let key = PrivateKey::from_bytes(vector.scheme, &[0x01; 32]).unwrap();
+        let wallet = Wallet::from_private_key(key, Network::Mainnet);
+        assert_eq!(wallet.get_address(), vector.sender);
+
+        let signed = wallet.sign_transaction(vector.recipient, AMOUNT, FEE, NONCE);
+        assert_eq!(signed.public_key, vector.public_key, "{}", vector.scheme);
+        assert_eq!(signed.signature, vector.signature, "{}", vector.scheme);
+        assert!(Wallet::verify_transaction(
+            &transaction(vector),
+            Network::Mainnet
+        ));
+    }
+}
+
+#[test]
+fn signature_covers_every_field_and_the_chain() {
+    for vector in VECTORS {
+        let tx = transaction(vector);
+        let payload = signing_payload(&tx, MAINNET_CHAIN_ID).unwrap();
+        assert_ne!(
+            signing_payload(&tx, Network::Testnet.chain_id()),
+            Some(payload)
+        );
+
+        let changes: [fn(&mut WalletTransaction); 5] = [
+            |tx| tx.recipient.push('x'),
+            |tx| tx.amount += 1,
+            |tx| tx.fee += 1,
+            |tx| tx.nonce += 1,
+            |tx| {
+                tx.scheme = match tx.scheme {
+                    Scheme::Ed25519 => Scheme::P256,
+                    _ => Scheme::Ed25519,
+                }
+            },
+        ];
+        for change in changes {
+            let mut changed = tx.clone();
+            change(&mut changed);
+            assert!(
+                !Wallet::verify_transaction(&changed, Network::Mainnet),
+                "{}",
+                vector.scheme
+            );
+        }
+    }
+}
+
+#[test]
+fn testnet_transfer_fails_on_a_mainnet_chain() {
+    let config = Config {
+        difficulty: 1,
+        network: Network::Mainnet,
+        ..Config::default()
+    };
+    let miner = Wallet::with_network(Network::Mainnet).get_address();
+    let mut chain = BlockChain::with_store(config, miner, Box::new(MemoryStore::new())).unwrap();
+
+    let recipient = Wallet::with_network(Network::Testnet).get_address();
+    let tx =
+        Wallet::with_network(Network::Testnet).sign_transaction(&recipient, AMOUNT, FEE, NONCE);
+    assert!(Wallet::verify_transaction(&tx, Network::Testnet));
+    assert!(!Wallet::verify_transaction(&tx, Network::Mainnet));
+    assert!(!chain.add_transaction(&tx));
+}
@@ -0,0 +1,171 @@
+//! Transfers the mempool and `validate_block` both turn down: a key that is not the one
+//! of the sender, and a nonce that was used already or skips one.
+
+use blockchain::config::Config;
+use blockchain::core::blockchain::block::Block;
+use blockchain::core::blockchain::difficulty::meets_target;
+use blockchain::core::blockchain::transaction::{OutPoint, Transaction};
+use blockchain::core::blockchain::validation::InvalidReason;
+use blockchain::core::blockchain::{AddBlockError, BlockChain, Serialization};
+use blockchain::core::storage::MemoryStore;
+use blockchain::core::wallet::{Wallet, WalletTransaction};
+
+const REWARD: u64 = 50;
+
+fn config() -> Config {
+    Config {
+        difficulty: 1,
+        retarget_interval: 1_000_000,
+        reward: REWARD,
+        mining_threads: 1,
+        ..Config::default()
+    }
+}
+
+/// A chain whose first block pays `REWARD` to `miner`.
+fn chain(miner: &Wallet) -> BlockChain {
+    BlockChain::with_store(config(), miner.get_address(), Box::new(MemoryStore::new())).unwrap()
+}
+
+fn mine(chain: &mut BlockChain, count: usize) {
+    for _ in 0..count {
+        assert!(chain.mining());
+    }
+}
+
+/// The output of the coinbase of the block at `height`.
+fn coinbase_output(chain: &BlockChain, height: usize) -> OutPoint {
+    OutPoint {
+        txid: Transaction::try_deserialization(&chain[height].transactions[0])
+            .unwrap()
+            .txid(),
+        index: 0,
+    }
+}
+
+/// `tx` spending `inputs` worth `value`, the rest back to the sender.
+fn funded(tx: &WalletTransaction, inputs: Vec<OutPoint>, value: u64) -> Vec<u8> {
+    Transaction::from_wallet_transaction(tx, inputs, value - tx.amount - tx.fee).serialization()
+}
+
+/// Adds the block on top of the tip holding an empty coinbase and `transactions`.
+fn add_block(chain: &mut BlockChain, transactions: Vec<Vec<u8>>) -> Result<(), AddBlockError> {
+    let height = chain.len();
+    let target = chain.next_target();
+    let mut block = Block::new(0, chain.last_block().hash(), target);
+    block.transactions =
+        vec![Transaction::coinbase(height as u64, vec![0xaa; 34], 0).serialization()];
+    block.transactions.extend(transactions);
+    block.update_merkle_root();
+    while !meets_target(&block.hash(), target) {
+        block.header.nonce += 1;
+    }
+    chain.add_block(block)
+}
+
+fn assert_invalid(result: Result<(), AddBlockError>, reason: InvalidReason) {
+    match result {
+        Err(AddBlockError::Invalid(e)) => assert_eq!(e.reason, reason),
+        other => panic!("expected {:?}, got {:?}", reason, other),
+    }
+}
+
+#[test]
+fn transfer_signed_by_another_key_is_refused() {
+    let miner = Wallet::new();
+    let mut chain = chain(&miner);
+    let recipient = Wallet::new().get_address();
+
+    // a valid signature by a key that is not the one the sender address derives from
+    let thief = Wallet::new();
+    let forged = Wallet::new_from(
+        thief.scheme(),
+        &hex::encode(thief.private_key().to_bytes()),
+        &miner.get_address(),
+    )
+    .unwrap()
+    .sign_transaction(&recipient, 10, 1, 0);
+    assert!(!Wallet::is_signed_by_sender(&forged));
+    assert!(!chain.add_transaction(&forged));
+    assert!(chain.mempool().is_empty());
+
+    let input = coinbase_output(&chain, 1);
+    assert_invalid(
+        add_block(
+            &mut chain,
+            vec![funded(&forged, vec![input.clone()], REWARD)],
+        ),
+        InvalidReason::KeyMismatch(1),
+    );
+
+    // the same transfer signed by the sender goes through
+    let signed = miner.sign_transaction(&recipient, 10, 1, 0);
+    assert!(Wallet::is_signed_by_sender(&signed));
+    add_block(&mut chain, vec![funded(&signed, vec![input], REWARD)]).unwrap();
+    assert_eq!(chain.account(&recipient).balance, 10);
+}
+
+#[test]
+fn pool_takes_each_nonce_once_and_in_order() {
+    let miner = Wallet::new();
+    let mut chain = chain(&miner);
+    let recipient = Wallet::new().get_address();
+
+    let first = miner.sign_transaction(&recipient, 10, 1, 0);
+    assert!(chain.add_transaction(&first));
+    // the same transfer again, another one with its nonce, and one skipping a nonce
+    assert!(!chain.add_transaction(&first));
+    assert!(!chain.add_transaction(&miner.sign_transaction(&recipient, 11, 1, 0)));
+    assert!(!chain.add_transaction(&miner.sign_transaction(&recipient, 10, 1, 2)));
+    assert_eq!(chain.mempool().len(), 1);
+
+    let second = miner.sign_transaction(&recipient, 10, 1, 1);
+    assert!(chain.add_transaction(&second));
+    assert_eq!(chain.next_nonce(&miner.get_address()), 2);
+
+    // once confirmed, they cannot be replayed
+    mine(&mut chain, 1);
+    assert!(chain.mempool().is_empty());
+    assert_eq!(chain.account(&miner.get_address()).nonce, 2);
+    assert!(!chain.add_transaction(&first));
+    assert!(!chain.add_transaction(&second));
+    assert!(chain.mempool().is_empty());
+    assert_eq!(chain.account(&recipient).balance, 20);
+}
+
+#[test]
+fn block_takes_each_nonce_once_and_in_order() {
+    let miner = Wallet::new();
+    let mut chain = chain(&miner);
+    mine(&mut chain, 3);
+    let recipient = Wallet::new().get_address();
+    let inputs: Vec<OutPoint> = (1..=4).map(|h| coinbase_output(&chain, h)).collect();
+    let pay =
+        |tx: &WalletTransaction, input: usize| funded(tx, vec![inputs[input].clone()], REWARD);
+
+    let first = miner.sign_transaction(&recipient, 10, 1, 0);
+    add_block(&mut chain, vec![pay(&first, 0)]).unwrap();
+
+    // replayed with other funds
+    assert_invalid(
+        add_block(&mut chain, vec![pay(&first, 1)]),
+        InvalidReason::BadNonce(1),
+    );
+    // skipping nonce 1
+    let third = miner.sign_transaction(&recipient, 10, 1, 2);
+    assert_invalid(
+        add_block(&mut chain, vec![pay(&third, 1)]),
+        InvalidReason::BadNonce(1),
+    );
+    // two transfers with nonce 1
+    let second = miner.sign_transaction(&recipient, 10, 1, 1);
+    let other_second = miner.sign_transaction(&recipient, 5, 1, 1);
+    assert_invalid(
+        add_block(&mut chain, vec![pay(&second, 1), pay(&other_second, 2)]),
+        InvalidReason::BadNonce(2),
+    );
+
+    add_block(&mut chain, vec![pay(&second, 1), pay(&third, 2)]).unwrap();
+    assert_eq!(chain.account(&miner.get_address()).nonce, 3);
+    assert_eq!(chain.account(&recipient).balance, 30);
+}
@@ -0,0 +1,56 @@
+//! Txids cover what the sender signed, not how the node funded the transfer.
+
+use blockchain::core::blockchain::transaction::{txid_of, OutPoint, Transaction};
+use blockchain::core::blockchain::Serialization;
+use blockchain::core::wallet::{Wallet, WalletTransaction};
+
+fn signed() -> WalletTransaction {
+    let recipient = Wallet::new().get_address();
+    Wallet::new().sign_transaction(&recipient, 50, 1, 0)
+}
+
+fn outpoint(byte: u8, index: u32) -> OutPoint {
+    OutPoint {
+        txid: vec![byte; 32],
+        index,
+    }
+}
+
+#[test]
+fn funding_a_transfer_again_keeps_its_txid() {
+    let tx = signed();
+    let first = Transaction::from_wallet_transaction(&tx, vec![outpoint(1, 0)], 49);
+    let again = Transaction::from_wallet_transaction(&tx, vec![outpoint(2, 1), outpoint(3, 0)], 0);
+    assert_ne!(first.serialization(), again.serialization());
+    assert_eq!(first.txid(), again.txid());
+    assert_eq!(txid_of(&first.serialization()), first.txid());
+    assert_eq!(txid_of(&again.serialization()), first.txid());
+}
+
+#[test]
+fn txid_changes_with_what_was_signed() {
+    let tx = signed();
+    let txid = Transaction::from_wallet_transaction(&tx, vec![outpoint(1, 0)], 0).txid();
+
+    let changes: [fn(&mut WalletTransaction); 4] = [
+        |tx| tx.amount += 1,
+        |tx| tx.fee += 1,
+        |tx| tx.nonce += 1,
+        |tx| tx.signature.replace_range(..2, "00"),
+    ];
+    for change in changes {
+        let mut changed = tx.clone();
+        change(&mut changed);
+        let changed = Transaction::from_wallet_transaction(&changed, vec![outpoint(1, 0)], 0);
+        assert_ne!(changed.txid(), txid);
+    }
+}
+
+#[test]
+fn coinbases_of_different_blocks_differ() {
+    let address = Wallet::new().get_address().into_bytes();
+    let first = Transaction::coinbase(1, address.clone(), 50);
+    let second = Transaction::coinbase(2, address, 50);
+    assert_ne!(first.txid(), second.txid());
+    assert_eq!(txid_of(&first.serialization()), first.txid());
+}